@@ -0,0 +1,36 @@
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    #[cfg(feature = "node")]
+    napi_build::setup();
+
+    // Reported by /health/ready. Image builds without a .git directory pass
+    // GIT_HASH in the environment instead.
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=GIT_HASH");
+    let hash = std::env::var("GIT_HASH").ok().or_else(git_hash).unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_HASH={}", hash);
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
+fn git_hash() -> Option<String> {
+    // Rebuild when HEAD moves, whether by checkout or by commit to the branch.
+    // Missing paths would force a rebuild every time, so only watch real ones.
+    let branch = git(&["symbolic-ref", "-q", "HEAD"]);
+    for name in ["HEAD", "packed-refs"].into_iter().chain(branch.as_deref()) {
+        if let Some(path) = git(&["rev-parse", "--git-path", name]) {
+            if Path::new(&path).exists() {
+                println!("cargo:rerun-if-changed={}", path);
+            }
+        }
+    }
+    git(&["rev-parse", "--short=12", "HEAD"])
+}
@@ -0,0 +1,15 @@
+#![no_main]
+
+use last_words_crypto::AeadDecryptRequest;
+use last_words_crypto_fuzz::{Base64, check, service};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (Base64, Base64, Base64, Option<String>)| {
+    let (ciphertext, key, nonce, additional_data) = input;
+    check(service().aead_decrypt(AeadDecryptRequest {
+        ciphertext: ciphertext.encode(),
+        key: key.secret(),
+        nonce: nonce.encode(),
+        additional_data,
+    }));
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use last_words_crypto::AeadEncryptRequest;
+use last_words_crypto_fuzz::{Base64, check, service};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (String, Base64, Option<String>)| {
+    let (plaintext, key, additional_data) = input;
+    check(service().aead_encrypt(AeadEncryptRequest {
+        plaintext: plaintext.into(),
+        key: key.secret(),
+        additional_data,
+    }));
+});
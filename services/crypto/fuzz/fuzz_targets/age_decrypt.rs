@@ -0,0 +1,20 @@
+#![no_main]
+
+use std::sync::OnceLock;
+
+use last_words_crypto::{AgeDecryptRequest, AgeIdentity, Secret};
+use last_words_crypto_fuzz::{Base64, check, service};
+use libfuzzer_sys::fuzz_target;
+
+// X25519 only: scrypt stanzas would spend each run deriving keys.
+fn identity() -> Secret {
+    static IDENTITY: OnceLock<Secret> = OnceLock::new();
+    IDENTITY.get_or_init(|| service().age_keygen().unwrap().identity).clone()
+}
+
+fuzz_target!(|file: Base64| {
+    check(service().age_decrypt(AgeDecryptRequest {
+        file: file.encode(),
+        identity: AgeIdentity::X25519 { identity: identity() },
+    }));
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use last_words_crypto::KdfRequest;
+use last_words_crypto_fuzz::{Base64, check, service};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (String, Option<Base64>, Option<u32>, Option<u32>, Option<u32>)| {
+    let (password, salt, memory, iterations, parallelism) = input;
+    // Bound the cost so each run stays fast; limits themselves are still hit.
+    check(service().kdf_argon2id(KdfRequest {
+        password: password.into(),
+        salt: salt.map(|salt| salt.encode()),
+        memory: Some(memory.map_or(8, |m| m % 1024)),
+        iterations: Some(iterations.map_or(1, |t| t % 3)),
+        parallelism: Some(parallelism.map_or(1, |p| p % 5)),
+    }));
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use last_words_crypto::KeyUnwrapRequest;
+use last_words_crypto_fuzz::{Base64, check, service};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (Base64, Base64, Base64)| {
+    let (master_key, wrapped_key, salt) = input;
+    check(service().key_unwrap(KeyUnwrapRequest {
+        master_key: master_key.secret(),
+        wrapped_key: wrapped_key.encode(),
+        salt: salt.encode(),
+    }));
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use last_words_crypto::KeyWrapRequest;
+use last_words_crypto_fuzz::{Base64, check, service};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (Base64, Base64)| {
+    let (master_key, user_key) = input;
+    check(service().key_wrap(KeyWrapRequest {
+        master_key: master_key.secret(),
+        user_key: user_key.secret(),
+    }));
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use last_words_crypto::*;
+use libfuzzer_sys::fuzz_target;
+
+// Every request body the HTTP server and the N-API binding accept.
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_json::from_slice::<KdfRequest>(data);
+    let _ = serde_json::from_slice::<AeadEncryptRequest>(data);
+    let _ = serde_json::from_slice::<AeadDecryptRequest>(data);
+    let _ = serde_json::from_slice::<KeyWrapRequest>(data);
+    let _ = serde_json::from_slice::<KeyUnwrapRequest>(data);
+    let _ = serde_json::from_slice::<ShamirSplitRequest>(data);
+    let _ = serde_json::from_slice::<ShamirCombineRequest>(data);
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use last_words_crypto::ShamirCombineRequest;
+use last_words_crypto_fuzz::{Base64, check, service};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|shares: Vec<Base64>| {
+    check(service().shamir_combine(ShamirCombineRequest {
+        shares: shares.iter().map(Base64::secret).collect(),
+    }));
+});
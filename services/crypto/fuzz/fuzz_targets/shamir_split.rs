@@ -0,0 +1,14 @@
+#![no_main]
+
+use last_words_crypto::ShamirSplitRequest;
+use last_words_crypto_fuzz::{Base64, check, service};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (Base64, u8, u8)| {
+    let (secret, threshold, shares) = input;
+    check(service().shamir_split(ShamirSplitRequest {
+        secret: secret.secret(),
+        threshold,
+        shares,
+    }));
+});
@@ -0,0 +1,53 @@
+//! Shared inputs for the fuzz targets.
+//!
+//! Every target builds requests from [`Base64`] fields, feeds them to one
+//! `CryptoBoundaryService` method and passes the result to [`check`]: the
+//! method may fail, but only with a `CryptoError`, never by panicking.
+
+use std::sync::OnceLock;
+
+use arbitrary::Arbitrary;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use last_words_crypto::{CryptoBoundaryService, CryptoError, Secret};
+
+pub fn service() -> &'static CryptoBoundaryService {
+    static SERVICE: OnceLock<CryptoBoundaryService> = OnceLock::new();
+    SERVICE.get_or_init(CryptoBoundaryService::new)
+}
+
+/// A base64 field: usually valid base64 of arbitrary bytes, sometimes junk.
+#[derive(Debug, Arbitrary)]
+pub enum Base64 {
+    Valid(Vec<u8>),
+    Raw(String),
+}
+
+impl Base64 {
+    pub fn encode(&self) -> String {
+        match self {
+            Base64::Valid(bytes) => STANDARD.encode(bytes),
+            Base64::Raw(raw) => raw.clone(),
+        }
+    }
+
+    pub fn secret(&self) -> Secret {
+        self.encode().into()
+    }
+}
+
+/// Fails the run unless `result` is a success or a typed error.
+pub fn check<T>(result: Result<T, CryptoError>) {
+    if let Err(e) = result {
+        // Exhaustive on purpose: a new variant must be looked at here.
+        match e {
+            CryptoError::InvalidInput(_)
+            | CryptoError::EncryptionFailed(_)
+            | CryptoError::DecryptionFailed(_)
+            | CryptoError::KeyDerivationFailed(_)
+            | CryptoError::Base64Error(_)
+            | CryptoError::Argon2Error(_)
+            | CryptoError::SecureMemory(_)
+            | CryptoError::SelfTestFailed(_) => {}
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! XChaCha20-Poly1305 (IETF) behind libsodium's interface.
+//!
+//! With the `sodium` feature the service uses libsodium through sodiumoxide.
+//! Without it (wasm32, where libsodium is unavailable) the RustCrypto
+//! `chacha20poly1305` crate provides the same construction with the same
+//! `xchacha20poly1305_ietf` API, so ciphertexts are byte-for-byte identical.
+
+#[cfg(feature = "sodium")]
+pub use sodiumoxide::crypto::aead::xchacha20poly1305_ietf;
+
+#[cfg(not(feature = "sodium"))]
+pub use self::pure as xchacha20poly1305_ietf;
+
+/// Initialises the active backend; must succeed before any other call.
+#[cfg(feature = "sodium")]
+#[allow(clippy::result_unit_err)]
+pub fn init() -> Result<(), ()> {
+    sodiumoxide::init()
+}
+
+#[cfg(not(feature = "sodium"))]
+#[allow(clippy::result_unit_err)]
+pub fn init() -> Result<(), ()> {
+    Ok(())
+}
+
+/// The pure-Rust backend, always built so it can be checked against libsodium.
+pub mod pure {
+    use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+    use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+    use rand::{RngCore, rngs::OsRng};
+    use zeroize::Zeroize;
+
+    pub const KEYBYTES: usize = 32;
+    pub const NONCEBYTES: usize = 24;
+
+    pub struct Key(pub [u8; KEYBYTES]);
+
+    pub struct Nonce(pub [u8; NONCEBYTES]);
+
+    impl Key {
+        pub fn from_slice(bs: &[u8]) -> Option<Key> {
+            bs.try_into().ok().map(Key)
+        }
+    }
+
+    impl Nonce {
+        pub fn from_slice(bs: &[u8]) -> Option<Nonce> {
+            bs.try_into().ok().map(Nonce)
+        }
+    }
+
+    impl Drop for Key {
+        fn drop(&mut self) {
+            self.0.zeroize();
+        }
+    }
+
+    pub fn gen_key() -> Key {
+        let mut key = Key([0u8; KEYBYTES]);
+        OsRng.fill_bytes(&mut key.0);
+        key
+    }
+
+    pub fn gen_nonce() -> Nonce {
+        let mut nonce = Nonce([0u8; NONCEBYTES]);
+        OsRng.fill_bytes(&mut nonce.0);
+        nonce
+    }
+
+    pub fn seal(m: &[u8], ad: Option<&[u8]>, n: &Nonce, k: &Key) -> Vec<u8> {
+        XChaCha20Poly1305::new((&k.0).into())
+            .encrypt(XNonce::from_slice(&n.0), Payload { msg: m, aad: ad.unwrap_or(&[]) })
+            .expect("XChaCha20-Poly1305 message too long")
+    }
+
+    #[allow(clippy::result_unit_err)]
+    pub fn open(c: &[u8], ad: Option<&[u8]>, n: &Nonce, k: &Key) -> Result<Vec<u8>, ()> {
+        XChaCha20Poly1305::new((&k.0).into())
+            .decrypt(XNonce::from_slice(&n.0), Payload { msg: c, aad: ad.unwrap_or(&[]) })
+            .map_err(|_| ())
+    }
+}
+
+#[cfg(all(test, feature = "sodium"))]
+mod tests {
+    use super::pure;
+    use sodiumoxide::crypto::aead::xchacha20poly1305_ietf as sodium;
+
+    #[test]
+    fn test_pure_backend_matches_libsodium() {
+        super::init().unwrap();
+        let key = sodium::gen_key();
+        let nonce = sodium::gen_nonce();
+        let pure_key = pure::Key::from_slice(&key.0).unwrap();
+        let pure_nonce = pure::Nonce::from_slice(&nonce.0).unwrap();
+
+        let sealed = sodium::seal(b"last words", Some(b"aad"), &nonce, &key);
+        assert_eq!(pure::seal(b"last words", Some(b"aad"), &pure_nonce, &pure_key), sealed);
+        assert_eq!(pure::open(&sealed, Some(b"aad"), &pure_nonce, &pure_key).unwrap(), b"last words");
+
+        let sealed = pure::seal(b"", None, &pure_nonce, &pure_key);
+        assert_eq!(sodium::open(&sealed, None, &nonce, &key).unwrap(), b"");
+        assert!(pure::open(&sealed, Some(b"x"), &pure_nonce, &pure_key).is_err());
+    }
+}
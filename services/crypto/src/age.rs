@@ -0,0 +1,514 @@
+//! The age file format (age-encryption.org/v1) with X25519 and scrypt recipients.
+//!
+//! Files written here open with the `age` CLI and files written by `age` open
+//! here, so a release export stays readable without this service. Stanzas
+//! wrap a 16-byte file key with HKDF-SHA256 and ChaCha20-Poly1305, as in
+//! [`crate::hpke`]; the payload is age's STREAM construction of 64 KiB
+//! ChaCha20-Poly1305 chunks. Only the binary format and its PEM armor are
+//! supported; plugin recipients and SSH keys are not.
+
+use base64::{Engine as _, engine::general_purpose};
+use bech32::{FromBase32, ToBase32, Variant};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use rand::{RngCore, rngs::OsRng};
+use secrecy::ExposeSecret;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use crate::{AgeIdentity, AgeRecipient, CryptoError};
+
+pub const VERSION_LINE: &str = "age-encryption.org/v1";
+
+/// The work factor (log2 of scrypt's N) used when none is given; age's default.
+pub const DEFAULT_WORK_FACTOR: u8 = 18;
+/// The highest work factor accepted either way; 2^18 costs 256 MiB of memory.
+pub const MAX_WORK_FACTOR: u8 = 18;
+
+const FILE_KEY_BYTES: usize = 16;
+const WRAPPED_KEY_BYTES: usize = FILE_KEY_BYTES + 16;
+const STREAM_NONCE_BYTES: usize = 16;
+const CHUNK_BYTES: usize = 64 * 1024;
+const TAG_BYTES: usize = 16;
+const COLUMNS: usize = 64;
+
+const X25519_LABEL: &[u8] = b"age-encryption.org/v1/X25519";
+const SCRYPT_LABEL: &[u8] = b"age-encryption.org/v1/scrypt";
+const RECIPIENT_HRP: &str = "age";
+const IDENTITY_HRP: &str = "age-secret-key-";
+
+const ARMOR_BEGIN: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+const ARMOR_END: &str = "-----END AGE ENCRYPTED FILE-----";
+
+type FileKey = Zeroizing<[u8; FILE_KEY_BYTES]>;
+
+fn malformed(what: &str) -> CryptoError {
+    CryptoError::InvalidInput(format!("Malformed age file: {}", what))
+}
+
+fn hkdf(ikm: &[u8], salt: &[u8], info: &[u8]) -> Zeroizing<[u8; 32]> {
+    let mut okm = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand(info, okm.as_mut())
+        .expect("32 bytes is a valid HKDF-SHA256 length");
+    okm
+}
+
+/// ChaCha20-Poly1305 with an all-zero nonce; every wrapping key is used once.
+fn wrap_file_key(key: &[u8; 32], file_key: &FileKey) -> Vec<u8> {
+    ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(&Nonce::default(), file_key.as_slice())
+        .expect("file key fits in one ChaCha20-Poly1305 message")
+}
+
+fn unwrap_file_key(key: &[u8; 32], body: &[u8]) -> Option<FileKey> {
+    let opened = Zeroizing::new(ChaCha20Poly1305::new(Key::from_slice(key)).decrypt(&Nonce::default(), body).ok()?);
+    let mut file_key = Zeroizing::new([0u8; FILE_KEY_BYTES]);
+    if opened.len() != FILE_KEY_BYTES {
+        return None;
+    }
+    file_key.copy_from_slice(&opened);
+    Some(file_key)
+}
+
+fn header_mac(file_key: &FileKey, header: &[u8]) -> Hmac<Sha256> {
+    let key = hkdf(file_key.as_slice(), &[], b"header");
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key.as_slice()).expect("HMAC takes any key length");
+    mac.update(header);
+    mac
+}
+
+fn x25519_key(
+    shared: &x25519_dalek::SharedSecret,
+    share: &PublicKey,
+    recipient: &PublicKey,
+) -> Result<Zeroizing<[u8; 32]>, CryptoError> {
+    if !shared.was_contributory() {
+        return Err(CryptoError::InvalidInput("X25519 key is a low-order point".to_string()));
+    }
+    let salt = [share.as_bytes().as_slice(), recipient.as_bytes()].concat();
+    Ok(hkdf(shared.as_bytes(), &salt, X25519_LABEL))
+}
+
+fn scrypt_key(passphrase: &str, salt: &[u8], work_factor: u8) -> Result<Zeroizing<[u8; 32]>, CryptoError> {
+    if !(1..=MAX_WORK_FACTOR).contains(&work_factor) {
+        return Err(CryptoError::InvalidInput(format!("scrypt work factor must be 1 to {}", MAX_WORK_FACTOR)));
+    }
+    let params = scrypt::Params::new(work_factor, 8, 1, 32)
+        .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    scrypt::scrypt(passphrase.as_bytes(), &[SCRYPT_LABEL, salt].concat(), &params, key.as_mut())
+        .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+    Ok(key)
+}
+
+/// Encodes an X25519 public key as an `age1…` recipient.
+pub fn encode_recipient(public_key: &PublicKey) -> String {
+    bech32::encode(RECIPIENT_HRP, public_key.as_bytes().to_base32(), Variant::Bech32)
+        .expect("age recipients are valid Bech32")
+}
+
+/// Encodes an X25519 secret key as an `AGE-SECRET-KEY-1…` identity.
+pub fn encode_identity(secret_key: &StaticSecret) -> Zeroizing<String> {
+    let encoded = Zeroizing::new(
+        bech32::encode(IDENTITY_HRP, secret_key.as_bytes().to_base32(), Variant::Bech32)
+            .expect("age identities are valid Bech32"),
+    );
+    Zeroizing::new(encoded.to_uppercase())
+}
+
+fn decode_bech32(encoded: &str, hrp: &str) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+    let kind = if hrp == RECIPIENT_HRP { "recipient" } else { "identity" };
+    let invalid = || CryptoError::InvalidInput(format!("Not an age {}", kind));
+    let (found, data, variant) = bech32::decode(encoded).map_err(|_| invalid())?;
+    if found != hrp || variant != Variant::Bech32 {
+        return Err(invalid());
+    }
+    let bytes = Zeroizing::new(Vec::<u8>::from_base32(&data).map_err(|_| invalid())?);
+    if bytes.len() != 32 {
+        return Err(invalid());
+    }
+    Ok(bytes)
+}
+
+pub fn parse_recipient(recipient: &str) -> Result<PublicKey, CryptoError> {
+    let bytes = decode_bech32(recipient, RECIPIENT_HRP)?;
+    Ok(PublicKey::from(<[u8; 32]>::try_from(bytes.as_slice()).expect("length checked")))
+}
+
+pub fn parse_identity(identity: &str) -> Result<StaticSecret, CryptoError> {
+    let bytes = decode_bech32(identity, IDENTITY_HRP)?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&bytes);
+    Ok(StaticSecret::from(*key))
+}
+
+/// Generates an X25519 identity and returns it with its recipient.
+pub fn generate_identity() -> (String, Zeroizing<String>) {
+    let secret_key = StaticSecret::random_from_rng(OsRng);
+    (encode_recipient(&PublicKey::from(&secret_key)), encode_identity(&secret_key))
+}
+
+struct Stanza {
+    tag: String,
+    args: Vec<String>,
+    body: Vec<u8>,
+}
+
+impl Stanza {
+    fn write(&self, header: &mut String) {
+        header.push_str("->");
+        for arg in std::iter::once(&self.tag).chain(&self.args) {
+            header.push(' ');
+            header.push_str(arg);
+        }
+        header.push('\n');
+        // The body's last line is always shorter than a full line, even if empty.
+        let body = general_purpose::STANDARD_NO_PAD.encode(&self.body);
+        for line in body.as_bytes().chunks(COLUMNS) {
+            header.push_str(std::str::from_utf8(line).expect("base64 is ASCII"));
+            header.push('\n');
+        }
+        if body.len().is_multiple_of(COLUMNS) {
+            header.push('\n');
+        }
+    }
+}
+
+fn stanza_for(recipient: &AgeRecipient, file_key: &FileKey) -> Result<Stanza, CryptoError> {
+    match recipient {
+        AgeRecipient::X25519 { recipient } => {
+            let recipient = parse_recipient(recipient)?;
+            let ephemeral = StaticSecret::random_from_rng(OsRng);
+            let share = PublicKey::from(&ephemeral);
+            let key = x25519_key(&ephemeral.diffie_hellman(&recipient), &share, &recipient)?;
+            Ok(Stanza {
+                tag: "X25519".to_string(),
+                args: vec![general_purpose::STANDARD_NO_PAD.encode(share.as_bytes())],
+                body: wrap_file_key(&key, file_key),
+            })
+        }
+        AgeRecipient::Scrypt { passphrase, work_factor } => {
+            let work_factor = work_factor.unwrap_or(DEFAULT_WORK_FACTOR);
+            let mut salt = [0u8; 16];
+            OsRng.fill_bytes(&mut salt);
+            let key = scrypt_key(passphrase.expose_secret(), &salt, work_factor)?;
+            Ok(Stanza {
+                tag: "scrypt".to_string(),
+                args: vec![general_purpose::STANDARD_NO_PAD.encode(salt), work_factor.to_string()],
+                body: wrap_file_key(&key, file_key),
+            })
+        }
+    }
+}
+
+fn chunk_nonce(counter: u64, last: bool) -> Nonce {
+    let mut nonce = Nonce::default();
+    nonce[3..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+fn payload_cipher(file_key: &FileKey, nonce: &[u8]) -> ChaCha20Poly1305 {
+    ChaCha20Poly1305::new(Key::from_slice(hkdf(file_key.as_slice(), nonce, b"payload").as_slice()))
+}
+
+/// Encrypts `plaintext` to the binary age format.
+pub fn encrypt(plaintext: &[u8], recipients: &[AgeRecipient]) -> Result<Vec<u8>, CryptoError> {
+    if recipients.is_empty() {
+        return Err(CryptoError::InvalidInput("An age file needs at least one recipient".to_string()));
+    }
+    // The spec forbids mixing: a passphrase must not be one of several ways in.
+    if recipients.len() > 1 && recipients.iter().any(|r| matches!(r, AgeRecipient::Scrypt { .. })) {
+        return Err(CryptoError::InvalidInput("An scrypt recipient must be the only recipient".to_string()));
+    }
+
+    let mut file_key = Zeroizing::new([0u8; FILE_KEY_BYTES]);
+    OsRng.fill_bytes(file_key.as_mut());
+
+    let mut header = format!("{}\n", VERSION_LINE);
+    for recipient in recipients {
+        stanza_for(recipient, &file_key)?.write(&mut header);
+    }
+    header.push_str("---");
+    let mac = header_mac(&file_key, header.as_bytes()).finalize().into_bytes();
+    header.push(' ');
+    header.push_str(&general_purpose::STANDARD_NO_PAD.encode(mac));
+    header.push('\n');
+
+    let mut file = header.into_bytes();
+    let mut nonce = [0u8; STREAM_NONCE_BYTES];
+    OsRng.fill_bytes(&mut nonce);
+    file.extend_from_slice(&nonce);
+
+    let cipher = payload_cipher(&file_key, &nonce);
+    let chunks: Vec<&[u8]> = if plaintext.is_empty() { vec![&[]] } else { plaintext.chunks(CHUNK_BYTES).collect() };
+    for (counter, chunk) in chunks.iter().enumerate() {
+        let sealed = cipher
+            .encrypt(&chunk_nonce(counter as u64, counter + 1 == chunks.len()), *chunk)
+            .map_err(|_| CryptoError::EncryptionFailed("Failed to encrypt age payload".to_string()))?;
+        file.extend_from_slice(&sealed);
+    }
+    Ok(file)
+}
+
+struct Header<'a> {
+    stanzas: Vec<Stanza>,
+    /// Everything the MAC covers: the header up to and including `---`.
+    authenticated: &'a [u8],
+    mac: Vec<u8>,
+}
+
+fn next_line<'a>(file: &'a [u8], pos: &mut usize) -> Result<&'a str, CryptoError> {
+    let rest = &file[*pos..];
+    let end = rest.iter().position(|&b| b == b'\n').ok_or_else(|| malformed("truncated header"))?;
+    *pos += end + 1;
+    std::str::from_utf8(&rest[..end]).map_err(|_| malformed("header is not ASCII"))
+}
+
+fn parse_header(file: &[u8]) -> Result<(Header<'_>, &[u8]), CryptoError> {
+    let mut pos = 0;
+    if next_line(file, &mut pos)? != VERSION_LINE {
+        return Err(malformed("unsupported version"));
+    }
+
+    let mut stanzas = Vec::new();
+    loop {
+        let start = pos;
+        let line = next_line(file, &mut pos)?;
+        if let Some(mac) = line.strip_prefix("--- ") {
+            let header = Header {
+                stanzas,
+                authenticated: &file[..start + 3],
+                mac: general_purpose::STANDARD_NO_PAD.decode(mac).map_err(|_| malformed("invalid header MAC"))?,
+            };
+            return Ok((header, &file[pos..]));
+        }
+
+        let mut args = line
+            .strip_prefix("-> ")
+            .ok_or_else(|| malformed("expected a stanza"))?
+            .split(' ')
+            .map(str::to_string);
+        let tag = args.next().unwrap_or_default();
+        let args: Vec<String> = args.collect();
+        if tag.is_empty() || args.iter().any(String::is_empty) {
+            return Err(malformed("empty stanza argument"));
+        }
+
+        let mut body = String::new();
+        loop {
+            let line = next_line(file, &mut pos)?;
+            if line.len() > COLUMNS {
+                return Err(malformed("stanza body line too long"));
+            }
+            body.push_str(line);
+            if line.len() < COLUMNS {
+                break;
+            }
+        }
+        let body = general_purpose::STANDARD_NO_PAD.decode(&body).map_err(|_| malformed("invalid stanza body"))?;
+        stanzas.push(Stanza { tag, args, body });
+    }
+}
+
+fn decode_arg(arg: &str, len: usize) -> Result<Vec<u8>, CryptoError> {
+    general_purpose::STANDARD_NO_PAD
+        .decode(arg)
+        .ok()
+        .filter(|bytes| bytes.len() == len)
+        .ok_or_else(|| malformed("invalid stanza argument"))
+}
+
+fn unwrap_x25519(stanzas: &[Stanza], secret_key: &StaticSecret) -> Result<FileKey, CryptoError> {
+    let public_key = PublicKey::from(secret_key);
+    for stanza in stanzas.iter().filter(|stanza| stanza.tag == "X25519") {
+        let [share] = stanza.args.as_slice() else {
+            return Err(malformed("X25519 stanza takes one argument"));
+        };
+        if stanza.body.len() != WRAPPED_KEY_BYTES {
+            return Err(malformed("invalid X25519 stanza body"));
+        }
+        let share = PublicKey::from(<[u8; 32]>::try_from(decode_arg(share, 32)?).expect("length checked"));
+        let key = x25519_key(&secret_key.diffie_hellman(&share), &share, &public_key)?;
+        if let Some(file_key) = unwrap_file_key(&key, &stanza.body) {
+            return Ok(file_key);
+        }
+    }
+    Err(CryptoError::DecryptionFailed("No age recipient matches this identity".to_string()))
+}
+
+fn unwrap_scrypt(stanzas: &[Stanza], passphrase: &str) -> Result<FileKey, CryptoError> {
+    let [stanza] = stanzas else {
+        return Err(malformed("an scrypt stanza must be the only stanza"));
+    };
+    if stanza.tag != "scrypt" {
+        return Err(CryptoError::DecryptionFailed("The age file is not passphrase-encrypted".to_string()));
+    }
+    let [salt, work_factor] = stanza.args.as_slice() else {
+        return Err(malformed("scrypt stanza takes two arguments"));
+    };
+    if stanza.body.len() != WRAPPED_KEY_BYTES {
+        return Err(malformed("invalid scrypt stanza body"));
+    }
+    // Decimal without leading zeros, as the spec requires.
+    let work_factor: u8 = Some(work_factor.as_str())
+        .filter(|w| w.bytes().all(|b| b.is_ascii_digit()) && !w.starts_with('0'))
+        .and_then(|w| w.parse().ok())
+        .ok_or_else(|| malformed("invalid scrypt work factor"))?;
+
+    let key = scrypt_key(passphrase, &decode_arg(salt, 16)?, work_factor)?;
+    unwrap_file_key(&key, &stanza.body).ok_or_else(|| CryptoError::DecryptionFailed("Incorrect passphrase".to_string()))
+}
+
+/// Decrypts a binary age file with `identity`.
+pub fn decrypt(file: &[u8], identity: &AgeIdentity) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+    let (header, payload) = parse_header(file)?;
+    let file_key = match identity {
+        AgeIdentity::X25519 { identity } => unwrap_x25519(&header.stanzas, &parse_identity(identity.expose_secret())?)?,
+        AgeIdentity::Scrypt { passphrase } => unwrap_scrypt(&header.stanzas, passphrase.expose_secret())?,
+    };
+    header_mac(&file_key, header.authenticated)
+        .verify_slice(&header.mac)
+        .map_err(|_| CryptoError::DecryptionFailed("age header MAC mismatch".to_string()))?;
+
+    if payload.len() < STREAM_NONCE_BYTES {
+        return Err(malformed("truncated payload"));
+    }
+    let (nonce, mut rest) = payload.split_at(STREAM_NONCE_BYTES);
+    let cipher = payload_cipher(&file_key, nonce);
+    let mut plaintext = Zeroizing::new(Vec::with_capacity(rest.len()));
+    for counter in 0u64.. {
+        let (chunk, tail) = rest.split_at(rest.len().min(CHUNK_BYTES + TAG_BYTES));
+        let last = tail.is_empty();
+        let opened = Zeroizing::new(
+            cipher
+                .decrypt(&chunk_nonce(counter, last), chunk)
+                .map_err(|_| CryptoError::DecryptionFailed("Failed to decrypt age payload".to_string()))?,
+        );
+        if last && opened.is_empty() && counter > 0 {
+            return Err(malformed("empty final chunk"));
+        }
+        plaintext.extend_from_slice(&opened);
+        if last {
+            break;
+        }
+        rest = tail;
+    }
+    Ok(plaintext)
+}
+
+/// Wraps a binary age file in age's PEM armor.
+pub fn armor(file: &[u8]) -> String {
+    let encoded = general_purpose::STANDARD.encode(file);
+    let mut armored = format!("{}\n", ARMOR_BEGIN);
+    for line in encoded.as_bytes().chunks(COLUMNS) {
+        armored.push_str(std::str::from_utf8(line).expect("base64 is ASCII"));
+        armored.push('\n');
+    }
+    armored.push_str(ARMOR_END);
+    armored.push('\n');
+    armored
+}
+
+pub fn is_armored(text: &str) -> bool {
+    text.trim_start().starts_with(ARMOR_BEGIN)
+}
+
+/// Removes age's PEM armor; surrounding whitespace is allowed.
+pub fn dearmor(text: &str) -> Result<Vec<u8>, CryptoError> {
+    let body = text
+        .trim()
+        .strip_prefix(ARMOR_BEGIN)
+        .and_then(|body| body.strip_suffix(ARMOR_END))
+        .ok_or_else(|| malformed("missing armor"))?;
+    let lines: Vec<&str> = body.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).collect();
+    let [first, lines @ .., last] = lines.as_slice() else {
+        return Err(malformed("empty armor"));
+    };
+    if !first.is_empty() || !last.is_empty() {
+        return Err(malformed("armor lines"));
+    }
+    let full_lines = lines.len().saturating_sub(1);
+    if lines.is_empty()
+        || lines[..full_lines].iter().any(|line| line.len() != COLUMNS)
+        || !(1..=COLUMNS).contains(&lines[full_lines].len())
+    {
+        return Err(malformed("armor line length"));
+    }
+    general_purpose::STANDARD.decode(lines.concat()).map_err(|_| malformed("invalid armor"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passphrase(work_factor: u8) -> AgeRecipient {
+        AgeRecipient::Scrypt { passphrase: "correct horse".into(), work_factor: Some(work_factor) }
+    }
+
+    #[test]
+    fn test_x25519_round_trip_across_chunks() {
+        let (recipient, identity) = generate_identity();
+        let identity = AgeIdentity::X25519 { identity: identity.as_str().into() };
+
+        for len in [0, 1, CHUNK_BYTES, CHUNK_BYTES + 1, 2 * CHUNK_BYTES] {
+            let plaintext = vec![0x5a; len];
+            let file = encrypt(&plaintext, &[AgeRecipient::X25519 { recipient: recipient.clone() }]).unwrap();
+            assert_eq!(*decrypt(&file, &identity).unwrap(), plaintext, "{} bytes", len);
+            assert_eq!(dearmor(&armor(&file)).unwrap(), file);
+
+            // Dropping the final chunk leaves a full chunk without the last flag.
+            if len > CHUNK_BYTES {
+                let truncated = &file[..file.len() - (len - CHUNK_BYTES) - TAG_BYTES];
+                assert!(decrypt(truncated, &identity).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_scrypt_round_trip_and_limits() {
+        let file = encrypt(b"last words", &[passphrase(10)]).unwrap();
+        let open = |passphrase: &str| decrypt(&file, &AgeIdentity::Scrypt { passphrase: passphrase.into() });
+        assert_eq!(*open("correct horse").unwrap(), b"last words");
+        assert!(matches!(open("battery staple"), Err(CryptoError::DecryptionFailed(_))));
+
+        let (recipient, _) = generate_identity();
+        assert!(encrypt(b"x", &[passphrase(10), AgeRecipient::X25519 { recipient }]).is_err());
+        assert!(encrypt(b"x", &[passphrase(MAX_WORK_FACTOR + 1)]).is_err());
+    }
+
+    #[test]
+    fn test_header_is_authenticated() {
+        let (recipient, identity) = generate_identity();
+        let identity = AgeIdentity::X25519 { identity: identity.as_str().into() };
+        let (other, _) = generate_identity();
+        let file = encrypt(b"last words", &[
+            AgeRecipient::X25519 { recipient },
+            AgeRecipient::X25519 { recipient: other },
+        ]).unwrap();
+
+        // Swapping the two stanzas keeps each one valid but changes the MAC input.
+        let end = file.windows(5).position(|w| w == b"\n--- ").unwrap() + 1;
+        let lines: Vec<&str> = std::str::from_utf8(&file[..end]).unwrap().lines().collect();
+        let header = [lines[0], lines[3], lines[4], lines[1], lines[2], ""].join("\n");
+        let swapped = [header.as_bytes(), &file[end..]].concat();
+        assert_eq!(swapped.len(), file.len());
+        assert!(decrypt(&file, &identity).is_ok());
+        assert!(matches!(decrypt(&swapped, &identity), Err(CryptoError::DecryptionFailed(_))));
+    }
+
+    #[test]
+    fn test_keys_round_trip() {
+        let (recipient, identity) = generate_identity();
+        assert!(recipient.starts_with("age1"));
+        assert!(identity.starts_with("AGE-SECRET-KEY-1"));
+        let secret_key = parse_identity(&identity).unwrap();
+        assert_eq!(encode_recipient(&PublicKey::from(&secret_key)), recipient);
+        assert!(parse_recipient(&identity).is_err());
+        assert!(parse_identity(&recipient).is_err());
+    }
+}
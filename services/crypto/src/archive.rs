@@ -0,0 +1,278 @@
+//! Release archives: the offline, self-describing export of a released will.
+//!
+//! An archive holds a content key wrapped (`key_wrap`) under a master key, and
+//! one AEAD envelope per secret encrypted under that content key. The master
+//! key is recovered either from a passphrase via Argon2id or by combining
+//! Shamir shares. Archives are written by
+//! [`CryptoBoundaryService::export_release_archive`] (`POST /release/archive`).
+//! Opening an archive only calls `CryptoBoundaryService` methods, so anything
+//! the service can decrypt, the archive can too.
+
+use base64::{Engine as _, engine::general_purpose};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::aead::xchacha20poly1305_ietf;
+use crate::{
+    AeadDecryptRequest, CryptoBoundaryService, CryptoError, KdfRequest,
+    KeyUnwrapRequest, KeyWrapRequest, Secret, ShamirCombineRequest,
+};
+
+pub const ARCHIVE_VERSION: u8 = 1;
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// How the master key of an archive is recovered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Unlock {
+    Passphrase {
+        salt: String,
+        memory: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+    Shares {
+        threshold: u8,
+    },
+}
+
+/// A single secret encrypted under the archive's content key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    pub version: u8,
+    pub name: String,
+    pub ciphertext: String,
+    pub nonce: String,
+    pub additional_data: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseArchive {
+    pub version: u8,
+    pub will_id: String,
+    pub unlock: Unlock,
+    pub wrapped_key: String,
+    pub wrap_salt: String,
+    pub envelopes: Vec<Envelope>,
+}
+
+/// Anything `lastwords-open` accepts on disk: a whole archive or one envelope.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ArchiveFile {
+    Archive(ReleaseArchive),
+    Envelope(Envelope),
+}
+
+/// Derives the raw 32-byte master key from a passphrase.
+pub fn passphrase_key(
+    service: &CryptoBoundaryService,
+    passphrase: &Secret,
+    unlock: &Unlock,
+) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+    let Unlock::Passphrase { salt, memory, iterations, parallelism } = unlock else {
+        return Err(CryptoError::InvalidInput("Archive is not passphrase protected".to_string()));
+    };
+
+    let response = service.kdf_argon2id(KdfRequest {
+        password: passphrase.clone(),
+        salt: Some(salt.clone()),
+        memory: Some(*memory),
+        iterations: Some(*iterations),
+        parallelism: Some(*parallelism),
+    })?;
+    response.key()
+}
+
+/// Combines base64 Shamir shares into the raw master key.
+pub fn shares_key(
+    service: &CryptoBoundaryService,
+    shares: Vec<Secret>,
+    unlock: &Unlock,
+) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+    let Unlock::Shares { threshold } = unlock else {
+        return Err(CryptoError::InvalidInput("Archive is not share protected".to_string()));
+    };
+    if shares.len() < *threshold as usize {
+        return Err(CryptoError::InvalidInput(
+            format!("At least {} shares are required", threshold)
+        ));
+    }
+
+    let response = service.shamir_combine(ShamirCombineRequest { shares })?;
+    Ok(Zeroizing::new(general_purpose::STANDARD.decode(response.secret.expose_secret())?))
+}
+
+impl ReleaseArchive {
+    /// Encrypts `items` (name, contents) under a fresh content key wrapped for `master_key`.
+    pub fn seal(
+        service: &CryptoBoundaryService,
+        will_id: &str,
+        unlock: Unlock,
+        master_key: &[u8],
+        items: &[(String, Zeroizing<Vec<u8>>)],
+    ) -> Result<Self, CryptoError> {
+        let content_key = xchacha20poly1305_ietf::gen_key();
+        let content_key_b64 = Secret::from(general_purpose::STANDARD.encode(content_key.0));
+
+        let wrapped = service.key_wrap(KeyWrapRequest {
+            master_key: general_purpose::STANDARD.encode(master_key).into(),
+            user_key: content_key_b64.clone(),
+        })?;
+
+        let envelopes = items
+            .iter()
+            .map(|(name, contents)| {
+                let encrypted = service.aead_seal(contents, &content_key_b64, Some(name))?;
+                Ok(Envelope {
+                    version: encrypted.version,
+                    name: name.clone(),
+                    ciphertext: encrypted.ciphertext,
+                    nonce: encrypted.nonce,
+                    additional_data: Some(name.clone()),
+                })
+            })
+            .collect::<Result<Vec<_>, CryptoError>>()?;
+
+        Ok(Self {
+            version: ARCHIVE_VERSION,
+            will_id: will_id.to_string(),
+            unlock,
+            wrapped_key: wrapped.wrapped_key,
+            wrap_salt: wrapped.salt,
+            envelopes,
+        })
+    }
+
+    /// Unwraps the archive's content key (base64) with the recovered master key.
+    pub fn content_key(
+        &self,
+        service: &CryptoBoundaryService,
+        master_key: &[u8],
+    ) -> Result<Secret, CryptoError> {
+        if self.version != ARCHIVE_VERSION {
+            return Err(CryptoError::InvalidInput(
+                format!("Unsupported archive version {}", self.version)
+            ));
+        }
+
+        let unwrapped = service.key_unwrap(KeyUnwrapRequest {
+            master_key: general_purpose::STANDARD.encode(master_key).into(),
+            wrapped_key: self.wrapped_key.clone(),
+            salt: self.wrap_salt.clone(),
+        })?;
+        Ok(unwrapped.unwrapped_key.clone())
+    }
+}
+
+impl Envelope {
+    /// Decrypts the envelope with a base64 content key. Contents are raw
+    /// bytes: an inheritance may hold PDFs and images as well as text.
+    pub fn open(
+        &self,
+        service: &CryptoBoundaryService,
+        content_key: &Secret,
+    ) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        if self.version != ENVELOPE_VERSION {
+            return Err(CryptoError::InvalidInput(
+                format!("Unsupported envelope version {}", self.version)
+            ));
+        }
+
+        service.aead_open(AeadDecryptRequest {
+            ciphertext: self.ciphertext.clone(),
+            key: content_key.clone(),
+            nonce: self.nonce.clone(),
+            additional_data: self.additional_data.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ShamirSplitRequest;
+
+    fn items() -> Vec<(String, Zeroizing<Vec<u8>>)> {
+        vec![
+            ("letter.txt".to_string(), Zeroizing::new(b"To my family".to_vec())),
+            ("accounts.txt".to_string(), Zeroizing::new(b"bank: 1234".to_vec())),
+            ("photo.jpg".to_string(), Zeroizing::new(vec![0xff, 0xd8, 0xff, 0xe0, 0x00, 0x80])),
+        ]
+    }
+
+    #[test]
+    fn test_passphrase_archive_round_trip() {
+        let service = CryptoBoundaryService::new();
+        let unlock = Unlock::Passphrase {
+            salt: general_purpose::STANDARD.encode([7u8; 32]),
+            memory: 4096,
+            iterations: 3,
+            parallelism: 1,
+        };
+        let master_key = passphrase_key(&service, &"correct horse".into(), &unlock).unwrap();
+        assert_eq!(master_key.len(), 32);
+
+        let archive = ReleaseArchive::seal(&service, "will-1", unlock, &master_key, &items()).unwrap();
+        let json = serde_json::to_string(&archive).unwrap();
+        let ArchiveFile::Archive(parsed) = serde_json::from_str(&json).unwrap() else {
+            panic!("archive parsed as envelope");
+        };
+
+        let master_key = passphrase_key(&service, &"correct horse".into(), &parsed.unlock).unwrap();
+        let content_key = parsed.content_key(&service, &master_key).unwrap();
+        let opened: Vec<_> = parsed
+            .envelopes
+            .iter()
+            .map(|e| (e.name.clone(), e.open(&service, &content_key).unwrap()))
+            .collect();
+        assert_eq!(opened, items());
+
+        let wrong_key = passphrase_key(&service, &"wrong horse".into(), &parsed.unlock).unwrap();
+        assert!(matches!(
+            parsed.content_key(&service, &wrong_key),
+            Err(CryptoError::DecryptionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_shares_archive_round_trip() {
+        let service = CryptoBoundaryService::new();
+        let master_key = [9u8; 32];
+        let split = service.shamir_split(ShamirSplitRequest {
+            secret: general_purpose::STANDARD.encode(master_key).into(),
+            threshold: 2,
+            shares: 3,
+        }).unwrap();
+
+        let archive = ReleaseArchive::seal(
+            &service,
+            "will-2",
+            Unlock::Shares { threshold: 2 },
+            &master_key,
+            &items(),
+        ).unwrap();
+
+        let too_few = shares_key(&service, split.shares[..1].to_vec(), &archive.unlock);
+        assert!(matches!(too_few, Err(CryptoError::InvalidInput(_))));
+
+        let recovered = shares_key(&service, split.shares[1..].to_vec(), &archive.unlock).unwrap();
+        let content_key = archive.content_key(&service, &recovered).unwrap();
+        let opened = archive.envelopes[1].open(&service, &content_key).unwrap();
+        assert_eq!(opened.as_slice(), b"bank: 1234");
+    }
+
+    #[test]
+    fn test_unknown_envelope_version_is_refused() {
+        let service = CryptoBoundaryService::new();
+        let master_key = [5u8; 32];
+        let archive = ReleaseArchive::seal(&service, "will-3", Unlock::Shares { threshold: 2 }, &master_key, &items())
+            .unwrap();
+        let content_key = archive.content_key(&service, &master_key).unwrap();
+
+        let mut envelope = archive.envelopes[0].clone();
+        envelope.version = ENVELOPE_VERSION + 1;
+        assert!(matches!(envelope.open(&service, &content_key), Err(CryptoError::InvalidInput(_))));
+    }
+}
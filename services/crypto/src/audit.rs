@@ -0,0 +1,252 @@
+//! Verification of the API's hash-chained audit log.
+//!
+//! `AuditChainService` in the NestJS API hashes
+//! `JSON.stringify(data, Object.keys(data).sort())`. A replacer array is an
+//! allowlist applied at every depth, so nested `details` keys that are not
+//! themselves top-level field names are dropped from the hash. This module
+//! reproduces that serialization byte for byte so exported chains can be
+//! checked offline.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Top-level fields covered by the hash, in `Array.prototype.sort` order.
+const HASHED_FIELDS: [&str; 14] = [
+    "action",
+    "details",
+    "duration",
+    "errorCode",
+    "errorMessage",
+    "ipAddress",
+    "operation",
+    "previousHash",
+    "resource",
+    "result",
+    "sessionId",
+    "timestamp",
+    "userAgent",
+    "userId",
+];
+
+/// One exported `AuditLog` row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: Option<String>,
+    pub timestamp: String,
+    pub user_id: Option<String>,
+    pub session_id: Option<String>,
+    pub operation: String,
+    pub resource: String,
+    pub action: String,
+    pub result: String,
+    pub details: Option<Value>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub duration: Option<i64>,
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+    pub previous_hash: Option<String>,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainError {
+    Link {
+        index: usize,
+        expected: Option<String>,
+        actual: Option<String>,
+    },
+    Hash {
+        index: usize,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl std::fmt::Display for ChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainError::Link { index, expected, actual } => write!(
+                f,
+                "Chain break at entry {}: expected previousHash {:?}, got {:?}",
+                index, expected, actual
+            ),
+            ChainError::Hash { index, expected, actual } => write!(
+                f,
+                "Hash mismatch at entry {}: expected {}, got {}",
+                index, expected, actual
+            ),
+        }
+    }
+}
+
+impl AuditEntry {
+    /// Computes the hash the API stored for this entry.
+    ///
+    /// Optional fields the API left `undefined` come back from the database as
+    /// `null`; they were omitted when the hash was taken, so they are omitted
+    /// here too. `previousHash` is the exception: the first entry hashed an
+    /// explicit `null`.
+    pub fn compute_hash(&self) -> String {
+        let mut data = serde_json::Map::new();
+        let mut put = |key: &str, value: Option<Value>| {
+            if let Some(value) = value.filter(|v| !v.is_null()) {
+                data.insert(key.to_string(), value);
+            }
+        };
+        put("timestamp", Some(Value::from(self.timestamp.clone())));
+        put("userId", self.user_id.clone().map(Value::from));
+        put("sessionId", self.session_id.clone().map(Value::from));
+        put("operation", Some(Value::from(self.operation.clone())));
+        put("resource", Some(Value::from(self.resource.clone())));
+        put("action", Some(Value::from(self.action.clone())));
+        put("result", Some(Value::from(self.result.clone())));
+        put("details", self.details.clone());
+        put("ipAddress", self.ip_address.clone().map(Value::from));
+        put("userAgent", self.user_agent.clone().map(Value::from));
+        put("duration", self.duration.map(Value::from));
+        put("errorCode", self.error_code.clone().map(Value::from));
+        put("errorMessage", self.error_message.clone().map(Value::from));
+        data.insert(
+            "previousHash".to_string(),
+            self.previous_hash.clone().map(Value::from).unwrap_or(Value::Null),
+        );
+
+        let mut serialized = String::new();
+        stringify(&Value::Object(data), &mut serialized);
+        hex::encode(Sha256::digest(serialized.as_bytes()))
+    }
+}
+
+/// Checks linkage and hashes of an exported chain, oldest entry first.
+///
+/// Like `validateAuditChain` in the API, every problem is reported rather
+/// than stopping at the first one.
+pub fn verify_chain(entries: &[AuditEntry]) -> Vec<ChainError> {
+    let mut errors = Vec::new();
+    let mut previous: Option<String> = None;
+
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.previous_hash != previous {
+            errors.push(ChainError::Link {
+                index,
+                expected: previous.clone(),
+                actual: entry.previous_hash.clone(),
+            });
+        }
+
+        let expected = entry.compute_hash();
+        if expected != entry.hash {
+            errors.push(ChainError::Hash {
+                index,
+                expected,
+                actual: entry.hash.clone(),
+            });
+        }
+
+        previous = Some(entry.hash.clone());
+    }
+
+    errors
+}
+
+/// `JSON.stringify` with `HASHED_FIELDS` as the replacer allowlist.
+fn stringify(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            out.push('{');
+            let mut first = true;
+            for key in HASHED_FIELDS.iter().filter(|key| map.contains_key(**key)) {
+                if !first {
+                    out.push(',');
+                }
+                first = false;
+                out.push_str(&Value::from(*key).to_string());
+                out.push(':');
+                stringify(&map[*key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                stringify(item, out);
+            }
+            out.push(']');
+        }
+        Value::Number(n) => match n.as_f64() {
+            // JavaScript prints integral doubles without a fractional part
+            Some(f) if n.is_f64() && f.fract() == 0.0 && f.abs() < 1e21 => {
+                out.push_str(&format!("{}", f as i64));
+            }
+            _ => out.push_str(&n.to_string()),
+        },
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Hashes produced by the API's calculateHash under Node.js
+    const FIRST_HASH: &str = "2637cf30baac9fa138ea63bce2f78765d5b93b3204fed1b8876bb9b20a9c91cf";
+    const SECOND_HASH: &str = "7e78df0171ffc8b07344335d0037a164f65834ec155bbba37ffbd8f98d41e67d";
+
+    fn chain() -> Vec<AuditEntry> {
+        serde_json::from_value(serde_json::json!([
+            {
+                "id": "a1",
+                "timestamp": "2025-01-02T03:04:05.678Z",
+                "userId": "user-1",
+                "sessionId": null,
+                "operation": "cryptography",
+                "resource": "crypto_service",
+                "action": "encrypt",
+                "result": "success",
+                "details": { "resource": "will", "userId": "u", "extra": 1, "nested": [{ "action": "x" }] },
+                "duration": 12,
+                "previousHash": null,
+                "hash": FIRST_HASH
+            },
+            {
+                "id": "a2",
+                "timestamp": "2025-01-02T03:04:06.000Z",
+                "operation": "authentication",
+                "resource": "user_session",
+                "action": "login",
+                "result": "failure",
+                "errorCode": "E1",
+                "errorMessage": "bad \"pw\"\n",
+                "previousHash": FIRST_HASH,
+                "hash": SECOND_HASH
+            }
+        ])).unwrap()
+    }
+
+    #[test]
+    fn test_verify_api_chain() {
+        let entries = chain();
+        assert_eq!(entries[0].compute_hash(), FIRST_HASH);
+        assert!(verify_chain(&entries).is_empty());
+    }
+
+    #[test]
+    fn test_verify_detects_tampering() {
+        let mut entries = chain();
+        entries[0].action = "decrypt".to_string();
+
+        let errors = verify_chain(&entries);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], ChainError::Hash { index: 0, .. }));
+
+        entries.swap(0, 1);
+        let errors = verify_chain(&entries);
+        assert!(errors.iter().any(|e| matches!(e, ChainError::Link { index: 0, .. })));
+    }
+}
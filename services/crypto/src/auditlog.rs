@@ -0,0 +1,415 @@
+//! The crypto service's own hash-chained audit log.
+//!
+//! Each [`AuditRecord`] commits to the one before it through `previous_hash`.
+//! Its `hash` is the hex SHA-256 of the RFC 8785 canonical JSON of the record
+//! without its `hash` member, so nested `details` are covered in full (unlike
+//! the API's chain, see [`crate::audit`]) and any JCS implementation can
+//! recompute it from an export.
+//!
+//! The records are also the leaves of an RFC 6962 Merkle tree (see
+//! [`crate::merkle`]); each leaf's data is the canonical JSON of the whole
+//! record. Signed tree heads commit to a prefix of the log, so one record's
+//! audit path proves it was logged without replaying the chain.
+//!
+//! A log opened on a file ([`AuditLog::open_file`]) writes each record there
+//! as a JSON line before the append returns, and picks the chain up again
+//! after a restart. Without a file the log lasts only as long as the process.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::VerifyingKey;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::merkle::{self, Hash};
+use crate::{
+    AuditChainBreak, AuditChainBreakReason, AuditEvent, AuditRecord, AuditTreeHead, CryptoError,
+    SignedTreeHead, canonical, signing,
+};
+
+/// The signing context of tree heads, reserved so `/sign` cannot mint one.
+pub const TREE_HEAD_CONTEXT: &str = "service/audit-tree-head";
+pub const TREE_HEAD_VERSION: u8 = 1;
+
+/// An [`AuditRecord`] without its `hash`: what the hash is taken over.
+#[derive(Serialize)]
+struct Unhashed<'a> {
+    sequence: u64,
+    timestamp: &'a DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a AuditEvent,
+    previous_hash: &'a Option<String>,
+}
+
+/// The hash `record` should carry.
+pub fn record_hash(record: &AuditRecord) -> Result<String, CryptoError> {
+    let unhashed = Unhashed {
+        sequence: record.sequence,
+        timestamp: &record.timestamp,
+        event: &record.event,
+        previous_hash: &record.previous_hash,
+    };
+    Ok(hex::encode(Sha256::digest(canonical::to_vec(&unhashed)?)))
+}
+
+/// Checks a whole chain, first record first, and reports where it first breaks.
+pub fn verify_chain(records: &[AuditRecord]) -> Result<(), AuditChainBreak> {
+    let mut previous: Option<&str> = None;
+    for (index, record) in records.iter().enumerate() {
+        let broken = |reason, expected: Option<String>, actual: Option<String>| AuditChainBreak {
+            index,
+            reason,
+            expected,
+            actual,
+        };
+        if record.sequence != index as u64 {
+            return Err(broken(
+                AuditChainBreakReason::Sequence,
+                Some(index.to_string()),
+                Some(record.sequence.to_string()),
+            ));
+        }
+        if record.previous_hash.as_deref() != previous {
+            return Err(broken(
+                AuditChainBreakReason::Link,
+                previous.map(str::to_string),
+                record.previous_hash.clone(),
+            ));
+        }
+        // A record that cannot be canonicalized cannot have been hashed either.
+        let expected = record_hash(record).ok();
+        if expected.as_deref() != Some(record.hash.as_str()) {
+            return Err(broken(AuditChainBreakReason::Hash, expected, Some(record.hash.clone())));
+        }
+        previous = Some(&record.hash);
+    }
+    Ok(())
+}
+
+/// The record's Merkle leaf hash.
+pub fn leaf_hash(record: &AuditRecord) -> Result<Hash, CryptoError> {
+    Ok(merkle::leaf_hash(&canonical::to_vec(record)?))
+}
+
+/// Parses a hex hash from a tree head or proof.
+pub fn decode_hash(hash: &str) -> Result<Hash, CryptoError> {
+    hex::decode(hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| CryptoError::InvalidInput("Hashes must be 32 bytes of hex".to_string()))
+}
+
+/// Checks the tree head's signature; says nothing about whether `public_key`
+/// is trusted.
+pub fn verify_tree_head(signed: &SignedTreeHead, public_key: &VerifyingKey) -> Result<bool, CryptoError> {
+    if signed.tree_head.version != TREE_HEAD_VERSION {
+        return Err(CryptoError::InvalidInput(format!("Unsupported tree head version {}", signed.tree_head.version)));
+    }
+    let signature = signing::decode_signature(&signed.signature)?;
+    signing::verify(public_key, TREE_HEAD_CONTEXT, &canonical::to_vec(&signed.tree_head)?, &signature)
+}
+
+/// Whether `audit_path` places `record` in the tree `tree_head` describes.
+pub fn verify_inclusion(
+    record: &AuditRecord,
+    tree_head: &AuditTreeHead,
+    audit_path: &[String],
+) -> Result<bool, CryptoError> {
+    let path = audit_path.iter().map(|hash| decode_hash(hash)).collect::<Result<Vec<_>, _>>()?;
+    Ok(merkle::verify_inclusion(
+        &leaf_hash(record)?,
+        record.sequence,
+        tree_head.tree_size,
+        &path,
+        &decode_hash(&tree_head.root_hash)?,
+    ))
+}
+
+#[derive(Debug, Default)]
+struct Chain {
+    records: Vec<AuditRecord>,
+    leaves: Vec<Hash>,
+    tree_head: Option<SignedTreeHead>,
+    file: Option<File>,
+}
+
+impl Chain {
+    /// The leaves of the tree of `tree_size`, or all of them if `None`.
+    fn tree(&self, tree_size: Option<u64>) -> Result<&[Hash], CryptoError> {
+        match tree_size {
+            None => Ok(&self.leaves),
+            Some(size) => self.leaves.get(..size as usize).ok_or_else(|| {
+                CryptoError::InvalidInput(format!("Tree size {} exceeds the log's {} records", size, self.leaves.len()))
+            }),
+        }
+    }
+}
+
+/// An append-only chain of records and its Merkle tree, held in memory and
+/// optionally mirrored to a file.
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    chain: RwLock<Chain>,
+}
+
+impl AuditLog {
+    fn read(&self) -> Result<RwLockReadGuard<'_, Chain>, CryptoError> {
+        self.chain.read().map_err(|_| CryptoError::SecureMemory("Audit log lock poisoned".to_string()))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, Chain>, CryptoError> {
+        self.chain.write().map_err(|_| CryptoError::SecureMemory("Audit log lock poisoned".to_string()))
+    }
+
+    /// Links `event` to the current head and returns the new record.
+    pub fn append(&self, event: AuditEvent, timestamp: DateTime<Utc>) -> Result<AuditRecord, CryptoError> {
+        self.append_unless(event, timestamp, |_| false).map(|record| record.expect("always appended"))
+    }
+
+    /// [`Self::append`], unless a record already logged matches `exists`; the
+    /// lookup and the append happen under one lock.
+    pub fn append_unless(
+        &self,
+        event: AuditEvent,
+        timestamp: DateTime<Utc>,
+        exists: impl Fn(&AuditRecord) -> bool,
+    ) -> Result<Option<AuditRecord>, CryptoError> {
+        for (name, value) in [
+            ("operation", &event.operation),
+            ("resource", &event.resource),
+            ("action", &event.action),
+            ("result", &event.result),
+        ] {
+            if value.trim().is_empty() {
+                return Err(CryptoError::InvalidInput(format!("{} must not be empty", name)));
+            }
+        }
+
+        let mut chain = self.write()?;
+        if chain.records.iter().any(exists) {
+            return Ok(None);
+        }
+        let mut record = AuditRecord {
+            sequence: chain.records.len() as u64,
+            timestamp,
+            event,
+            previous_hash: chain.records.last().map(|last| last.hash.clone()),
+            hash: String::new(),
+        };
+        record.hash = record_hash(&record)?;
+        let leaf = leaf_hash(&record)?;
+        if let Some(file) = &mut chain.file {
+            let mut line = canonical::to_vec(&record)?;
+            line.push(b'\n');
+            file.write_all(&line)
+                .and_then(|()| file.sync_data())
+                .map_err(|e| CryptoError::InvalidInput(format!("Failed to persist audit record: {}", e)))?;
+        }
+        chain.records.push(record.clone());
+        chain.leaves.push(leaf);
+        Ok(Some(record))
+    }
+
+    /// Loads the chain stored in `path`, one JSON record per line, and appends
+    /// every later record there too. The file is created if missing; a chain
+    /// that does not verify is refused. Returns the number of records loaded.
+    pub fn open_file(&self, path: &Path) -> Result<u64, CryptoError> {
+        let failed = |e: &dyn std::fmt::Display| CryptoError::InvalidInput(format!("{}: {}", path.display(), e));
+        let mut chain = self.write()?;
+        if !chain.records.is_empty() || chain.file.is_some() {
+            return Err(CryptoError::InvalidInput("The audit log is already in use".to_string()));
+        }
+
+        let file = OpenOptions::new().read(true).append(true).create(true).open(path).map_err(|e| failed(&e))?;
+        let mut records: Vec<AuditRecord> = Vec::new();
+        for line in BufReader::new(&file).lines() {
+            let line = line.map_err(|e| failed(&e))?;
+            records.push(serde_json::from_str(&line).map_err(|e| failed(&e))?);
+        }
+        if let Err(broken) = verify_chain(&records) {
+            return Err(failed(&format!("chain breaks at record {} ({:?})", broken.index, broken.reason)));
+        }
+
+        chain.leaves = records.iter().map(leaf_hash).collect::<Result<_, _>>()?;
+        chain.records = records;
+        chain.file = Some(file);
+        Ok(chain.records.len() as u64)
+    }
+
+    /// The first record's hash, which names this chain; `None` while the log
+    /// is empty.
+    pub fn log_id(&self) -> Result<Option<String>, CryptoError> {
+        Ok(self.read()?.records.first().map(|record| record.hash.clone()))
+    }
+
+    /// The newest record's hash, or `None` while the log is empty.
+    pub fn head(&self) -> Result<Option<String>, CryptoError> {
+        Ok(self.read()?.records.last().map(|record| record.hash.clone()))
+    }
+
+    pub fn records(&self) -> Result<Vec<AuditRecord>, CryptoError> {
+        Ok(self.read()?.records.clone())
+    }
+
+    /// The current tree size and root.
+    pub fn root(&self) -> Result<(u64, Hash), CryptoError> {
+        let chain = self.read()?;
+        Ok((chain.leaves.len() as u64, merkle::root(&chain.leaves)))
+    }
+
+    /// The leaf hash and audit path of record `sequence` in the tree of `tree_size`.
+    pub fn inclusion_proof(
+        &self,
+        sequence: u64,
+        tree_size: Option<u64>,
+    ) -> Result<(u64, Hash, Vec<Hash>), CryptoError> {
+        let chain = self.read()?;
+        let tree = chain.tree(tree_size)?;
+        if sequence >= tree.len() as u64 {
+            return Err(CryptoError::InvalidInput(format!(
+                "Record {} is not in a tree of {} records",
+                sequence,
+                tree.len()
+            )));
+        }
+        let index = sequence as usize;
+        Ok((tree.len() as u64, tree[index], merkle::inclusion_proof(tree, index)))
+    }
+
+    /// The proof that the tree of `first` is a prefix of the tree of `second`.
+    pub fn consistency_proof(&self, first: u64, second: Option<u64>) -> Result<(u64, Vec<Hash>), CryptoError> {
+        let chain = self.read()?;
+        let tree = chain.tree(second)?;
+        if first == 0 || first > tree.len() as u64 {
+            return Err(CryptoError::InvalidInput(format!(
+                "First tree size must be between 1 and {}",
+                tree.len()
+            )));
+        }
+        Ok((tree.len() as u64, merkle::consistency_proof(tree, first as usize)))
+    }
+
+    /// The most recently signed tree head.
+    pub fn tree_head(&self) -> Result<Option<SignedTreeHead>, CryptoError> {
+        Ok(self.read()?.tree_head.clone())
+    }
+
+    pub fn set_tree_head(&self, tree_head: SignedTreeHead) -> Result<(), CryptoError> {
+        self.write()?.tree_head = Some(tree_head);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    fn event(action: &str, details: Value) -> AuditEvent {
+        AuditEvent {
+            operation: "release".to_string(),
+            resource: "will".to_string(),
+            action: action.to_string(),
+            result: "success".to_string(),
+            user_id: Some("user-1".to_string()),
+            session_id: None,
+            details: Some(details),
+        }
+    }
+
+    fn log() -> AuditLog {
+        let log = AuditLog::default();
+        let at = Utc.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).unwrap();
+        log.append(event("approve", json!({ "approver": { "id": "exec-2", "role": "executor" } })), at).unwrap();
+        log.append(event("access", json!({ "beneficiary": "b-7" })), at).unwrap();
+        log
+    }
+
+    #[test]
+    fn test_record_hash_is_sha256_of_canonical_json() {
+        // Computed independently from the canonical JSON of the first record.
+        let records = log().records().unwrap();
+        assert_eq!(records[0].hash, "837bca9478e6e839dc7842a138ec151def8622e51af4599cf9004c1b56679958");
+        assert_eq!(records[1].previous_hash.as_deref(), Some(records[0].hash.as_str()));
+    }
+
+    #[test]
+    fn test_verify_reports_first_broken_link() {
+        let mut records = log().records().unwrap();
+        assert_eq!(verify_chain(&records), Ok(()));
+
+        // Nested details are hashed too.
+        records[0].event.details = Some(json!({ "approver": { "id": "exec-3", "role": "executor" } }));
+        let broken = verify_chain(&records).unwrap_err();
+        assert_eq!((broken.index, broken.reason), (0, AuditChainBreakReason::Hash));
+
+        let mut records = log().records().unwrap();
+        records.remove(0);
+        let broken = verify_chain(&records).unwrap_err();
+        assert_eq!((broken.index, broken.reason), (0, AuditChainBreakReason::Sequence));
+
+        let mut records = log().records().unwrap();
+        records[1].previous_hash = None;
+        records[1].hash = record_hash(&records[1]).unwrap();
+        let broken = verify_chain(&records).unwrap_err();
+        assert_eq!((broken.index, broken.reason), (1, AuditChainBreakReason::Link));
+    }
+
+    #[test]
+    fn test_tree_head_and_inclusion_proof() {
+        let log = log();
+        let records = log.records().unwrap();
+        let (tree_size, root) = log.root().unwrap();
+        let tree_head = AuditTreeHead {
+            version: TREE_HEAD_VERSION,
+            tree_size,
+            root_hash: hex::encode(root),
+            timestamp: records[1].timestamp,
+            kid: String::new(),
+            log_id: log.log_id().unwrap(),
+        };
+        assert_eq!(tree_head.log_id.as_deref(), Some(records[0].hash.as_str()));
+
+        for record in &records {
+            let (_, leaf, path) = log.inclusion_proof(record.sequence, None).unwrap();
+            assert_eq!(leaf, leaf_hash(record).unwrap());
+            let path: Vec<String> = path.iter().map(hex::encode).collect();
+            assert!(verify_inclusion(record, &tree_head, &path).unwrap());
+
+            let mut forged = record.clone();
+            forged.event.result = "failure".to_string();
+            assert!(!verify_inclusion(&forged, &tree_head, &path).unwrap());
+        }
+        assert!(log.inclusion_proof(2, None).is_err());
+        assert!(log.inclusion_proof(0, Some(3)).is_err());
+    }
+
+    #[test]
+    fn test_file_backed_log_survives_reopening() {
+        let path = std::env::temp_dir().join(format!("audit-log-{}.jsonl", std::process::id()));
+        let at = Utc.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).unwrap();
+        let first = AuditLog::default();
+        assert_eq!(first.open_file(&path).unwrap(), 0);
+        first.append(event("approve", json!({ "approver": "exec-2" })), at).unwrap();
+        first.append(event("access", json!({ "beneficiary": "b-7" })), at).unwrap();
+
+        let reopened = AuditLog::default();
+        assert_eq!(reopened.open_file(&path).unwrap(), 2);
+        assert_eq!(reopened.root().unwrap(), first.root().unwrap());
+        let record = reopened.append(event("access", json!({ "beneficiary": "b-8" })), at).unwrap();
+        assert_eq!((record.sequence, record.previous_hash), (2, first.head().unwrap()));
+        assert!(reopened.open_file(&path).is_err());
+
+        let tampered = std::fs::read_to_string(&path).unwrap().replace("b-7", "b-9");
+        std::fs::write(&path, tampered).unwrap();
+        let result = AuditLog::default().open_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(CryptoError::InvalidInput(message)) if message.contains("chain breaks at record 1")));
+    }
+}
@@ -0,0 +1,242 @@
+//! Operator tooling for the crypto boundary: key generation, KDF calibration,
+//! key wrapping, envelope inspection and audit chain verification.
+//! Operations are reported to the `AUDIT_EVENT_SINKS` sinks, if set.
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use base64::{Engine as _, engine::general_purpose};
+use clap::{Parser, Subcommand};
+use secrecy::ExposeSecret;
+use last_words_crypto::aead::xchacha20poly1305_ietf;
+use last_words_crypto::archive::{ArchiveFile, Envelope, Unlock};
+use last_words_crypto::audit::{self, AuditEntry};
+use last_words_crypto::auditlog;
+use last_words_crypto::events;
+use last_words_crypto::{
+    AuditLogResponse, CryptoBoundaryService, KdfRequest, KeyUnwrapRequest, KeyWrapRequest, Secret,
+    ShamirSplitRequest,
+};
+use zeroize::Zeroizing;
+
+#[derive(Parser)]
+#[command(name = "lastwords-crypto", version, about = "Last Words crypto boundary administration")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a random 32-byte key, optionally split into Shamir shares
+    Keygen {
+        /// Number of shares to split the key into
+        #[arg(long, requires = "threshold")]
+        shares: Option<u8>,
+        /// Shares required to recover the key
+        #[arg(long, requires = "shares")]
+        threshold: Option<u8>,
+    },
+    /// Time Argon2id at increasing memory costs to calibrate KdfRequest defaults
+    KdfBench {
+        /// Target duration of one derivation, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        target_ms: u64,
+        /// Largest memory cost to try, in KiB
+        #[arg(long, default_value_t = 1 << 20)]
+        max_memory: u32,
+        #[arg(long, default_value_t = 3)]
+        iterations: u32,
+        #[arg(long, default_value_t = 1)]
+        parallelism: u32,
+    },
+    /// Wrap a key under a master key (both base64), prompting for any key
+    /// not given as a file
+    Wrap {
+        /// File containing the base64 master key
+        #[arg(long)]
+        master_key_file: Option<PathBuf>,
+        /// File containing the base64 key to wrap
+        #[arg(long)]
+        user_key_file: Option<PathBuf>,
+    },
+    /// Unwrap a key previously wrapped under a master key
+    Unwrap {
+        /// File containing the base64 master key; prompted for if omitted
+        #[arg(long)]
+        master_key_file: Option<PathBuf>,
+        #[arg(long)]
+        wrapped_key: String,
+        #[arg(long)]
+        salt: String,
+    },
+    /// Print the headers of a release archive or envelope without decrypting it
+    Inspect {
+        file: PathBuf,
+    },
+    /// Verify a JSON export of the API's audit log, oldest entry first
+    VerifyAudit {
+        file: PathBuf,
+    },
+    /// Verify a `GET /audit/log` export of the crypto service's audit chain
+    VerifyAuditLog {
+        file: PathBuf,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("lastwords-crypto: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<ExitCode, Box<dyn Error>> {
+    events::install_from_env("none")?;
+    let service = CryptoBoundaryService::new();
+
+    match command {
+        Command::Keygen { shares, threshold } => {
+            let key = Secret::from(general_purpose::STANDARD.encode(xchacha20poly1305_ietf::gen_key().0));
+            println!("key: {}", key.expose_secret());
+            if let (Some(shares), Some(threshold)) = (shares, threshold) {
+                let split = service.shamir_split(ShamirSplitRequest { secret: key, threshold, shares })?;
+                for (i, share) in split.shares.iter().enumerate() {
+                    println!("share {}/{}: {}", i + 1, shares, share.expose_secret());
+                }
+            }
+        }
+        Command::KdfBench { target_ms, max_memory, iterations, parallelism } => {
+            kdf_bench(&service, Duration::from_millis(target_ms), max_memory, iterations, parallelism)?;
+        }
+        Command::Wrap { master_key_file, user_key_file } => {
+            let response = service.key_wrap(KeyWrapRequest {
+                master_key: read_secret(master_key_file.as_deref(), "Master key: ")?,
+                user_key: read_secret(user_key_file.as_deref(), "Key to wrap: ")?,
+            })?;
+            println!("{}", serde_json::to_string_pretty(&response)?);
+        }
+        Command::Unwrap { master_key_file, wrapped_key, salt } => {
+            let response = service.key_unwrap(KeyUnwrapRequest {
+                master_key: read_secret(master_key_file.as_deref(), "Master key: ")?,
+                wrapped_key,
+                salt,
+            })?;
+            println!("{}", serde_json::to_string_pretty(&response)?);
+        }
+        Command::Inspect { file } => {
+            match serde_json::from_slice::<ArchiveFile>(&fs::read(&file)?)? {
+                ArchiveFile::Archive(archive) => {
+                    println!("release archive v{}", archive.version);
+                    println!("will id:   {}", archive.will_id);
+                    match &archive.unlock {
+                        Unlock::Passphrase { memory, iterations, parallelism, .. } => println!(
+                            "unlock:    passphrase (argon2id m={} t={} p={})",
+                            memory, iterations, parallelism
+                        ),
+                        Unlock::Shares { threshold } => println!("unlock:    {} shamir shares", threshold),
+                    }
+                    println!("envelopes: {}", archive.envelopes.len());
+                    for envelope in &archive.envelopes {
+                        print_envelope(envelope);
+                    }
+                }
+                ArchiveFile::Envelope(envelope) => print_envelope(&envelope),
+            }
+        }
+        Command::VerifyAudit { file } => {
+            let entries: Vec<AuditEntry> = serde_json::from_slice(&fs::read(&file)?)?;
+            let errors = audit::verify_chain(&entries);
+            for error in &errors {
+                println!("{}", error);
+            }
+            if !errors.is_empty() {
+                println!("audit chain INVALID: {} problem(s) in {} entries", errors.len(), entries.len());
+                return Ok(ExitCode::FAILURE);
+            }
+            println!("audit chain valid: {} entries", entries.len());
+        }
+        Command::VerifyAuditLog { file } => {
+            let export: AuditLogResponse = serde_json::from_slice(&fs::read(&file)?)?;
+            if let Err(broken) = auditlog::verify_chain(&export.records) {
+                println!(
+                    "audit chain INVALID at record {} ({:?}): expected {:?}, got {:?}",
+                    broken.index, broken.reason, broken.expected, broken.actual
+                );
+                return Ok(ExitCode::FAILURE);
+            }
+            let head = export.records.last().map_or("none", |record| record.hash.as_str());
+            println!("audit chain valid: {} records, head {}", export.records.len(), head);
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Reads a base64 key from `path`, or prompts for it without echo, so keys
+/// never appear on the command line or in shell history.
+fn read_secret(path: Option<&Path>, prompt: &str) -> std::io::Result<Secret> {
+    let raw = Zeroizing::new(match path {
+        Some(path) => fs::read_to_string(path)?,
+        None => rpassword::prompt_password(prompt)?,
+    });
+    Ok(raw.trim().into())
+}
+
+fn print_envelope(envelope: &Envelope) {
+    let ciphertext_len = general_purpose::STANDARD
+        .decode(&envelope.ciphertext)
+        .map(|c| c.len().to_string())
+        .unwrap_or_else(|_| "invalid base64".to_string());
+    println!(
+        "  - {} (v{}, xchacha20poly1305, ciphertext {} bytes, aad {:?})",
+        envelope.name, envelope.version, ciphertext_len, envelope.additional_data
+    );
+}
+
+fn kdf_bench(
+    service: &CryptoBoundaryService,
+    target: Duration,
+    max_memory: u32,
+    iterations: u32,
+    parallelism: u32,
+) -> Result<(), Box<dyn Error>> {
+    println!("{:>12} {:>10} {:>12}", "memory KiB", "iterations", "duration ms");
+
+    let mut recommended = None;
+    let mut memory = 8 * 1024;
+    while memory <= max_memory {
+        let start = Instant::now();
+        service.kdf_argon2id(KdfRequest {
+            password: "benchmark".into(),
+            salt: None,
+            memory: Some(memory),
+            iterations: Some(iterations),
+            parallelism: Some(parallelism),
+        })?;
+        let elapsed = start.elapsed();
+        println!("{:>12} {:>10} {:>12}", memory, iterations, elapsed.as_millis());
+
+        if elapsed > target {
+            break;
+        }
+        recommended = Some(memory);
+        memory *= 2;
+    }
+
+    match recommended {
+        Some(memory) => println!(
+            "recommended: memory={} iterations={} parallelism={} (within {} ms)",
+            memory, iterations, parallelism, target.as_millis()
+        ),
+        None => println!("no tested memory cost finished within {} ms", target.as_millis()),
+    }
+    Ok(())
+}
@@ -0,0 +1,146 @@
+//! Offline decryption of exported release archives.
+//!
+//! Beneficiaries run this against files exported from Last Words to recover
+//! their inheritance without any network access. All cryptography goes through
+//! `CryptoBoundaryService`, exactly as the hosted service would perform it.
+//! Its operations are reported to the `AUDIT_EVENT_SINKS` sinks, if set.
+
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::Parser;
+use last_words_crypto::{CryptoBoundaryService, Secret};
+use last_words_crypto::archive::{self, ArchiveFile, Envelope, ReleaseArchive, Unlock};
+use last_words_crypto::events;
+use zeroize::Zeroizing;
+
+#[derive(Parser)]
+#[command(name = "lastwords-open", version, about = "Decrypt Last Words release archives offline")]
+struct Args {
+    /// Release archives or individual envelope files (JSON)
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Directory to write decrypted files into
+    #[arg(short, long)]
+    out: PathBuf,
+
+    /// File containing one base64 Shamir share (repeat for each share)
+    #[arg(long = "share")]
+    shares: Vec<PathBuf>,
+
+    /// File containing the base64 content key, for individual envelopes
+    #[arg(long)]
+    key_file: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("lastwords-open: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    events::install_from_env("none")?;
+    let service = CryptoBoundaryService::new();
+    fs::create_dir_all(&args.out)?;
+
+    for input in &args.inputs {
+        let file: ArchiveFile = serde_json::from_slice(&fs::read(input)?)
+            .map_err(|e| format!("{}: not a release archive or envelope: {}", input.display(), e))?;
+
+        match file {
+            ArchiveFile::Archive(archive) => {
+                let master_key = master_key(&service, &archive, &args.shares)?;
+                let content_key = archive.content_key(&service, &master_key)?;
+                let dir = args.out.join(file_name(&archive.will_id)?);
+                fs::create_dir_all(&dir)?;
+                for envelope in &archive.envelopes {
+                    write_envelope(&service, envelope, &content_key, &dir)?;
+                }
+                println!("{}: {} file(s) written to {}", input.display(), archive.envelopes.len(), dir.display());
+            }
+            ArchiveFile::Envelope(envelope) => {
+                let key_file = args.key_file.as_ref()
+                    .ok_or("individual envelopes need --key-file")?;
+                let content_key = read_secret(key_file)?;
+                let path = write_envelope(&service, &envelope, &content_key, &args.out)?;
+                println!("{}: written to {}", input.display(), path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn master_key(
+    service: &CryptoBoundaryService,
+    archive: &ReleaseArchive,
+    share_files: &[PathBuf],
+) -> Result<Zeroizing<Vec<u8>>, Box<dyn Error>> {
+    match &archive.unlock {
+        Unlock::Passphrase { .. } => {
+            let passphrase = Secret::from(
+                rpassword::prompt_password(format!("Passphrase for {}: ", archive.will_id))?
+            );
+            Ok(archive::passphrase_key(service, &passphrase, &archive.unlock)?)
+        }
+        Unlock::Shares { threshold } => {
+            let shares = if share_files.is_empty() {
+                (1..=*threshold)
+                    .map(|i| {
+                        let share = Zeroizing::new(
+                            rpassword::prompt_password(format!("Share {} of {}: ", i, threshold))?
+                        );
+                        Ok(share.trim().into())
+                    })
+                    .collect::<Result<Vec<_>, std::io::Error>>()?
+            } else {
+                share_files
+                    .iter()
+                    .map(|path| read_secret(path))
+                    .collect::<Result<Vec<_>, _>>()?
+            };
+            Ok(archive::shares_key(service, shares, &archive.unlock)?)
+        }
+    }
+}
+
+fn write_envelope(
+    service: &CryptoBoundaryService,
+    envelope: &Envelope,
+    content_key: &Secret,
+    dir: &Path,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let plaintext = envelope.open(service, content_key)
+        .map_err(|e| format!("{}: {}", envelope.name, e))?;
+    let path = dir.join(file_name(&envelope.name)?);
+
+    // Never clobber existing files; a rerun must not destroy earlier output.
+    let mut file = OpenOptions::new().write(true).create_new(true).open(&path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    file.write_all(&plaintext)?;
+    Ok(path)
+}
+
+/// Reads a base64 key or share from a file, trimming surrounding whitespace.
+fn read_secret(path: &Path) -> std::io::Result<Secret> {
+    let raw = Zeroizing::new(fs::read_to_string(path)?);
+    Ok(raw.trim().into())
+}
+
+/// Reduces an archive-supplied name to a single path component.
+fn file_name(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    Path::new(name)
+        .file_name()
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("invalid file name in archive: {:?}", name).into())
+}
@@ -0,0 +1,131 @@
+//! RFC 8785 JSON Canonicalization Scheme (JCS).
+//!
+//! The canonical form is what gets hashed and signed: no whitespace, object
+//! members sorted by the UTF-16 code units of their names, strings with only
+//! the mandatory escapes, and numbers printed as ECMAScript prints them, so
+//! any JCS implementation reproduces the same bytes. Input must be I-JSON:
+//! integers beyond ±(2^53 - 1) are rejected rather than silently rounded.
+
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+use crate::CryptoError;
+
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+fn invalid(reason: impl std::fmt::Display) -> CryptoError {
+    CryptoError::InvalidInput(format!("Cannot canonicalize JSON: {}", reason))
+}
+
+/// Serializes `value` in canonical form.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, CryptoError> {
+    let value = serde_json::to_value(value).map_err(invalid)?;
+    let mut out = Vec::new();
+    write_value(&value, &mut out)?;
+    Ok(out)
+}
+
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, CryptoError> {
+    Ok(String::from_utf8(to_vec(value)?).expect("canonical JSON is UTF-8"))
+}
+
+fn write_value(value: &Value, out: &mut Vec<u8>) -> Result<(), CryptoError> {
+    match value {
+        Value::Null => out.extend_from_slice(b"null"),
+        Value::Bool(true) => out.extend_from_slice(b"true"),
+        Value::Bool(false) => out.extend_from_slice(b"false"),
+        Value::Number(number) => write_number(number, out)?,
+        // serde_json escapes exactly what JCS requires, with lowercase \u00xx.
+        Value::String(string) => serde_json::to_writer(&mut *out, string).map_err(invalid)?,
+        Value::Array(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_value(item, out)?;
+            }
+            out.push(b']');
+        }
+        Value::Object(members) => {
+            let mut members: Vec<_> = members.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push(b'{');
+            for (i, (name, member)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                serde_json::to_writer(&mut *out, name).map_err(invalid)?;
+                out.push(b':');
+                write_value(member, out)?;
+            }
+            out.push(b'}');
+        }
+    }
+    Ok(())
+}
+
+fn write_number(number: &Number, out: &mut Vec<u8>) -> Result<(), CryptoError> {
+    if let Some(int) = number.as_i64() {
+        if int.unsigned_abs() > MAX_SAFE_INTEGER {
+            return Err(invalid(format!("{} is outside the I-JSON integer range", int)));
+        }
+        out.extend_from_slice(int.to_string().as_bytes());
+    } else if number.is_u64() {
+        return Err(invalid(format!("{} is outside the I-JSON integer range", number)));
+    } else {
+        let float = number.as_f64().filter(|f| f.is_finite()).ok_or_else(|| invalid("non-finite number"))?;
+        if float == 0.0 {
+            // ECMAScript prints negative zero as "0".
+            out.push(b'0');
+        } else {
+            out.extend_from_slice(ryu_js::Buffer::new().format_finite(float).as_bytes());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rfc8785_examples() {
+        // Section 3.2.2: literals, numbers and string escaping.
+        let input: Value = serde_json::from_str(r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#).unwrap();
+        assert_eq!(
+            to_string(&input).unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#,
+        );
+
+        // Section 3.2.3: names sort by UTF-16 code units, so U+1F600 (a
+        // surrogate pair) precedes U+FB33, unlike in a UTF-8 byte sort.
+        let input: Value = serde_json::from_str(r#"{
+            "\u20ac": "Euro Sign",
+            "\r": "Carriage Return",
+            "\ufb33": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\ud83d\ude00": "Emoji: Grinning Face",
+            "\u0080": "Control",
+            "\u00f6": "Latin Small Letter O With Diaeresis"
+        }"#).unwrap();
+        assert_eq!(to_string(&input).unwrap(), concat!(
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",",
+            "\"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",",
+            "\"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}",
+        ));
+    }
+
+    #[test]
+    fn test_numbers_outside_i_json_are_rejected() {
+        let safe = json!([9007199254740991i64, -9007199254740991i64, -0.0]);
+        assert_eq!(to_string(&safe).unwrap(), "[9007199254740991,-9007199254740991,0]");
+        assert!(to_vec(&json!(9007199254740992u64)).is_err());
+        assert!(to_vec(&json!({ "nested": [u64::MAX] })).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Signed release certificates.
+//!
+//! When a release completes, the service attests what happened: which will
+//! and release event, why it fired, who approved it, when, and the SHA-256 of
+//! the encrypted will content that was released. The attestation is signed as
+//! RFC 8785 canonical JSON in the [`CONTEXT`] context of [`crate::signing`],
+//! so a beneficiary or a court can check it without this service: fetch the
+//! key from `GET /keys/signing`, canonicalize `attestation`, and verify the
+//! Ed25519 signature over [`signing::signed_message`] of it.
+
+use base64::{Engine as _, engine::general_purpose};
+use ed25519_dalek::VerifyingKey;
+use sha2::{Digest, Sha256};
+
+use crate::{CryptoError, ReleaseAttestation, ReleaseCertificate, canonical, signing};
+
+pub const CONTEXT: &str = "service/release-certificate";
+pub const CERTIFICATE_VERSION: u8 = 1;
+
+/// The `content_hash` of encrypted will content.
+pub fn content_hash(encrypted_content: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(encrypted_content)))
+}
+
+pub fn check_content_hash(hash: &str) -> Result<(), CryptoError> {
+    let valid = hash
+        .strip_prefix("sha256:")
+        .is_some_and(|digest| digest.len() == 64 && digest.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')));
+    if !valid {
+        return Err(CryptoError::InvalidInput("content_hash must be sha256: and 64 lowercase hex digits".to_string()));
+    }
+    Ok(())
+}
+
+/// The exact bytes signed for `attestation`.
+pub fn signed_bytes(attestation: &ReleaseAttestation) -> Result<Vec<u8>, CryptoError> {
+    signing::signed_message(CONTEXT, &canonical::to_vec(attestation)?)
+}
+
+/// Checks the signature; says nothing about whether `public_key` is trusted.
+pub fn verify(certificate: &ReleaseCertificate, public_key: &VerifyingKey) -> Result<bool, CryptoError> {
+    if certificate.attestation.version != CERTIFICATE_VERSION {
+        return Err(CryptoError::InvalidInput(format!(
+            "Unsupported certificate version {}",
+            certificate.attestation.version
+        )));
+    }
+    let signature = signing::decode_signature(&certificate.signature)?;
+    let message = canonical::to_vec(&certificate.attestation)?;
+    signing::verify(public_key, CONTEXT, &message, &signature)
+}
+
+/// Whether base64 `encrypted_content` is what the certificate attests.
+pub fn content_matches(certificate: &ReleaseCertificate, encrypted_content: &str) -> Result<bool, CryptoError> {
+    let content = general_purpose::STANDARD.decode(encrypted_content)?;
+    Ok(content_hash(&content) == certificate.attestation.content_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::{
+        CryptoBoundaryService, ReleaseApproval, ReleaseCertificateRequest, ReleaseEventType,
+        VerifyReleaseCertificateRequest,
+    };
+
+    fn request(content: &[u8]) -> ReleaseCertificateRequest {
+        ReleaseCertificateRequest {
+            will_id: "will-1".to_string(),
+            release_event_id: "release-7".to_string(),
+            event_type: ReleaseEventType::AliveCheckFailed,
+            trigger_reason: "3 alive checks missed".to_string(),
+            approvals: vec![ReleaseApproval {
+                approved_by: "executor-2".to_string(),
+                approved_at: Utc.with_ymd_and_hms(2026, 3, 1, 9, 30, 0).unwrap(),
+            }],
+            released_at: Utc.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).unwrap(),
+            content_hash: content_hash(content),
+            kid: None,
+        }
+    }
+
+    #[test]
+    fn test_issue_then_verify_detects_tampering() {
+        let service = CryptoBoundaryService::new();
+        let jwk = service.generate_signing_key().unwrap();
+        let certificate = service.issue_release_certificate(request(b"sealed will")).unwrap();
+        assert_eq!(certificate.attestation.kid, jwk.kid);
+
+        let verify = |certificate: &ReleaseCertificate, content: &[u8]| {
+            service.verify_release_certificate(VerifyReleaseCertificateRequest {
+                certificate: certificate.clone(),
+                public_key: None,
+                encrypted_content: Some(general_purpose::STANDARD.encode(content)),
+            }).unwrap()
+        };
+        let response = verify(&certificate, b"sealed will");
+        assert!(response.valid);
+        assert_eq!(response.content_matches, Some(true));
+        assert_eq!(verify(&certificate, b"another will").content_matches, Some(false));
+
+        let mut tampered = certificate.clone();
+        tampered.attestation.trigger_reason = "manual".to_string();
+        assert!(!verify(&tampered, b"sealed will").valid);
+
+        let mut tampered = certificate;
+        tampered.attestation.approvals.clear();
+        assert!(!verify(&tampered, b"sealed will").valid);
+    }
+
+    #[test]
+    fn test_verifies_offline_from_canonical_json() {
+        let service = CryptoBoundaryService::new();
+        let jwk = service.generate_signing_key().unwrap();
+        let certificate = service.issue_release_certificate(request(b"sealed will")).unwrap();
+
+        // A verifier holding only the published JWK and the certificate JSON.
+        let json = serde_json::to_string(&certificate).unwrap();
+        let certificate: ReleaseCertificate = serde_json::from_str(&json).unwrap();
+        let x = general_purpose::URL_SAFE_NO_PAD.decode(&jwk.x).unwrap();
+        let public_key = VerifyingKey::from_bytes(&x.try_into().unwrap()).unwrap();
+        let signature = signing::decode_signature(&certificate.signature).unwrap();
+        let message = signed_bytes(&certificate.attestation).unwrap();
+        assert!(public_key.verify_strict(&message, &signature).is_ok());
+    }
+
+    #[test]
+    fn test_malformed_requests_are_rejected() {
+        let service = CryptoBoundaryService::new();
+        service.generate_signing_key().unwrap();
+
+        let mut bad_hash = request(b"sealed will");
+        bad_hash.content_hash = "sha256:ABC".to_string();
+        let mut no_reason = request(b"sealed will");
+        no_reason.trigger_reason = " ".to_string();
+        for req in [bad_hash, no_reason] {
+            assert!(matches!(service.issue_release_certificate(req), Err(CryptoError::InvalidInput(_))));
+        }
+    }
+}
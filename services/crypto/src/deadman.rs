@@ -0,0 +1,276 @@
+//! The dead man's switch: evaluates alive-check schedules against a clock.
+//!
+//! A check is pending until `nextCheckDue`, then overdue for
+//! `gracePeriodDays`. A check-in at any point before the grace period ends
+//! resets it; otherwise the check is missed and the next one is scheduled
+//! `intervalDays` after the missed due date. After `maxMissedBeforeTrigger`
+//! consecutive misses the switch triggers a release and deactivates the check.
+//!
+//! The API owns the rows: evaluation takes a row and returns it updated, with
+//! the events to act on. Evaluating again without new input emits nothing,
+//! and missed checks are caught up however long the scheduler was down.
+//! Catching up stops at the trigger, so the limit on
+//! `maxMissedBeforeTrigger` also bounds the work and events of one call.
+//!
+//! The server keeps the last row it saw of each check and evaluates those on
+//! its own interval too, so a switch triggers even while the API is quiet.
+//! Each trigger is logged once per check, however often it is reported.
+
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{AliveCheck, AliveCheckEvent, CryptoError, ReleaseEventType};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to, for replaying a schedule offline.
+pub struct ManualClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self { now: Mutex::new(now) }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().expect("clock lock poisoned") += by;
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().expect("clock lock poisoned") = now;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().expect("clock lock poisoned")
+    }
+}
+
+/// Upper limit on `intervalDays` and `gracePeriodDays`: ten years.
+pub const MAX_DAYS: u32 = 3660;
+/// Upper limit on `maxMissedBeforeTrigger`.
+pub const MAX_MISSED_BEFORE_TRIGGER: u32 = 100;
+
+fn check_schedule(check: &AliveCheck) -> Result<(), CryptoError> {
+    for (name, value, max) in [
+        ("intervalDays", check.interval_days, MAX_DAYS),
+        ("gracePeriodDays", check.grace_period_days, MAX_DAYS),
+        ("maxMissedBeforeTrigger", check.max_missed_before_trigger, MAX_MISSED_BEFORE_TRIGGER),
+    ] {
+        if value == 0 || value > max {
+            return Err(CryptoError::InvalidInput(format!("{} must be between 1 and {}", name, max)));
+        }
+    }
+    Ok(())
+}
+
+/// `at + by`, or an error where that falls outside the representable dates.
+fn add(at: DateTime<Utc>, by: Duration) -> Result<DateTime<Utc>, CryptoError> {
+    at.checked_add_signed(by)
+        .ok_or_else(|| CryptoError::InvalidInput(format!("{} plus {} days is out of range", at, by.num_days())))
+}
+
+/// Brings `check` up to `clock`'s time and returns what happened, oldest first.
+pub fn evaluate(check: &mut AliveCheck, clock: &dyn Clock) -> Result<Vec<AliveCheckEvent>, CryptoError> {
+    check_schedule(check)?;
+    let now = clock.now();
+    let interval = Duration::days(check.interval_days.into());
+    let grace = Duration::days(check.grace_period_days.into());
+
+    let mut events = Vec::new();
+    while check.is_active {
+        let due_at = check.next_check_due;
+        let grace_ends_at = add(due_at, grace)?;
+        if now < due_at {
+            check.is_overdue = false;
+            break;
+        }
+        if now < grace_ends_at {
+            if !check.is_overdue {
+                check.is_overdue = true;
+                events.push(AliveCheckEvent::Overdue {
+                    check_id: check.id.clone(),
+                    user_id: check.user_id.clone(),
+                    due_at,
+                    grace_ends_at,
+                });
+            }
+            break;
+        }
+
+        check.consecutive_missed = check.consecutive_missed.saturating_add(1);
+        events.push(AliveCheckEvent::Missed {
+            check_id: check.id.clone(),
+            user_id: check.user_id.clone(),
+            due_at,
+            consecutive_missed: check.consecutive_missed,
+        });
+        if check.consecutive_missed >= check.max_missed_before_trigger {
+            check.is_active = false;
+            check.is_overdue = true;
+            events.push(AliveCheckEvent::Triggered {
+                check_id: check.id.clone(),
+                user_id: check.user_id.clone(),
+                event_type: ReleaseEventType::AliveCheckFailed,
+                trigger_reason: format!("{} consecutive alive checks missed", check.consecutive_missed),
+                triggered_at: now,
+            });
+        } else {
+            check.next_check_due = add(due_at, interval)?;
+            check.is_overdue = false;
+        }
+    }
+    Ok(events)
+}
+
+/// Records a check-in. Time is brought up to date first, so a check-in
+/// after the switch triggered is refused (`false`) rather than undoing it.
+pub fn confirm(check: &mut AliveCheck, clock: &dyn Clock) -> Result<(bool, Vec<AliveCheckEvent>), CryptoError> {
+    let events = evaluate(check, clock)?;
+    if !check.is_active {
+        return Ok((false, events));
+    }
+    let now = clock.now();
+    check.last_check_at = Some(now);
+    check.next_check_due = add(now, Duration::days(check.interval_days.into()))?;
+    check.consecutive_missed = 0;
+    check.is_overdue = false;
+    Ok((true, events))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    /// Checked every 30 days, 7 days' grace, triggers on the third miss.
+    fn check() -> AliveCheck {
+        AliveCheck {
+            id: "check-1".to_string(),
+            user_id: "user-1".to_string(),
+            interval_days: 30,
+            grace_period_days: 7,
+            last_check_at: None,
+            next_check_due: start() + Duration::days(30),
+            consecutive_missed: 0,
+            max_missed_before_trigger: 3,
+            is_active: true,
+            is_overdue: false,
+        }
+    }
+
+    fn kinds(events: &[AliveCheckEvent]) -> Vec<&'static str> {
+        events
+            .iter()
+            .map(|event| match event {
+                AliveCheckEvent::Overdue { .. } => "overdue",
+                AliveCheckEvent::Missed { .. } => "missed",
+                AliveCheckEvent::Triggered { .. } => "triggered",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_missed_checks_lead_through_grace_to_trigger() {
+        let clock = ManualClock::new(start());
+        let mut check = check();
+        let mut step = |days: i64| {
+            clock.advance(Duration::days(days));
+            kinds(&evaluate(&mut check, &clock).unwrap())
+        };
+
+        assert!(step(29).is_empty());
+        assert_eq!(step(1), ["overdue"]);
+        assert!(step(3).is_empty(), "still in grace, nothing new");
+        assert_eq!(step(4), ["missed"]);
+        // Day 60 is the second due date; it is missed at day 67.
+        assert_eq!(step(23), ["overdue"]);
+        assert_eq!(step(7), ["missed"]);
+        assert_eq!(step(23), ["overdue"]);
+        assert_eq!(step(7), ["missed", "triggered"]);
+        assert!(step(365).is_empty(), "a triggered switch stays quiet");
+
+        assert!(!check.is_active);
+        assert_eq!(check.consecutive_missed, 3);
+    }
+
+    #[test]
+    fn test_check_in_during_grace_resets_the_count() {
+        let clock = ManualClock::new(start());
+        let mut check = check();
+        clock.advance(Duration::days(37));
+        assert_eq!(kinds(&evaluate(&mut check, &clock).unwrap()), ["missed"]);
+
+        clock.advance(Duration::days(25));
+        let (confirmed, events) = confirm(&mut check, &clock).unwrap();
+        assert!(confirmed);
+        assert_eq!(kinds(&events), ["overdue"]);
+        assert_eq!((check.consecutive_missed, check.is_overdue), (0, false));
+        assert_eq!(check.next_check_due, clock.now() + Duration::days(30));
+        assert_eq!(check.last_check_at, Some(clock.now()));
+    }
+
+    #[test]
+    fn test_downtime_is_caught_up_and_late_check_in_is_refused() {
+        let clock = ManualClock::new(start() + Duration::days(200));
+        let mut check = check();
+        let events = evaluate(&mut check, &clock).unwrap();
+        assert_eq!(kinds(&events), ["missed", "missed", "missed", "triggered"]);
+        let AliveCheckEvent::Triggered { triggered_at, event_type, .. } = &events[3] else { unreachable!() };
+        assert_eq!((*triggered_at, *event_type), (clock.now(), ReleaseEventType::AliveCheckFailed));
+
+        let (confirmed, events) = confirm(&mut check, &clock).unwrap();
+        assert!(!confirmed && events.is_empty());
+        assert!(!check.is_active);
+    }
+
+    #[test]
+    fn test_out_of_range_schedules_are_rejected() {
+        let clock = ManualClock::new(start());
+        let rejected = |change: fn(&mut AliveCheck)| {
+            let mut changed = check();
+            change(&mut changed);
+            matches!(evaluate(&mut changed, &clock), Err(CryptoError::InvalidInput(_)))
+        };
+        assert!(rejected(|check| check.interval_days = 0));
+        assert!(rejected(|check| check.grace_period_days = u32::MAX));
+        assert!(rejected(|check| check.max_missed_before_trigger = MAX_MISSED_BEFORE_TRIGGER + 1));
+
+        // Due dates at the end of time fail instead of overflowing.
+        clock.set(DateTime::<Utc>::MAX_UTC);
+        assert!(rejected(|check| check.next_check_due = DateTime::<Utc>::MAX_UTC));
+        let mut check = check();
+        check.next_check_due = DateTime::<Utc>::MAX_UTC;
+        assert!(matches!(confirm(&mut check, &clock), Err(CryptoError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_catch_up_stops_at_the_trigger() {
+        let clock = ManualClock::new(DateTime::<Utc>::MAX_UTC - Duration::days(MAX_DAYS.into()));
+        let mut check = check();
+        check.interval_days = 1;
+        check.grace_period_days = 1;
+        check.max_missed_before_trigger = MAX_MISSED_BEFORE_TRIGGER;
+        let events = evaluate(&mut check, &clock).unwrap();
+        assert_eq!(events.len() as u32, MAX_MISSED_BEFORE_TRIGGER + 1);
+        assert!(!check.is_active);
+    }
+}
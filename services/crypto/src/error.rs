@@ -0,0 +1,37 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CryptoError {
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("Encryption failed: {0}")]
+    EncryptionFailed(String),
+    #[error("Decryption failed: {0}")]
+    DecryptionFailed(String),
+    #[error("Key derivation failed: {0}")]
+    KeyDerivationFailed(String),
+    #[error("Base64 decode error: {0}")]
+    Base64Error(#[from] base64::DecodeError),
+    #[error("Argon2 error: {0}")]
+    Argon2Error(String),
+    #[error("Secure memory error: {0}")]
+    SecureMemory(String),
+    #[error("Self-test failed: {0}")]
+    SelfTestFailed(String),
+}
+
+impl CryptoError {
+    /// The variant name, for metric labels and structured logs.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CryptoError::InvalidInput(_) => "InvalidInput",
+            CryptoError::EncryptionFailed(_) => "EncryptionFailed",
+            CryptoError::DecryptionFailed(_) => "DecryptionFailed",
+            CryptoError::KeyDerivationFailed(_) => "KeyDerivationFailed",
+            CryptoError::Base64Error(_) => "Base64Error",
+            CryptoError::Argon2Error(_) => "Argon2Error",
+            CryptoError::SecureMemory(_) => "SecureMemory",
+            CryptoError::SelfTestFailed(_) => "SelfTestFailed",
+        }
+    }
+}
@@ -0,0 +1,434 @@
+//! Audit events for crypto operations, delivered to pluggable sinks.
+//!
+//! Every [`CryptoBoundaryService`](crate::CryptoBoundaryService) operation
+//! emits one [`OperationEvent`] through [`observe`]: which operation ran,
+//! which key it used (by id), who asked, how it ended and how long it took.
+//! The caller is whatever the transport set with [`as_caller`]; library and
+//! CLI calls have none. Synthetic work such as the self-tests runs under
+//! [`unobserved`] and emits nothing. The event has no field that can hold
+//! request data, so no sink can leak a key or plaintext however it is
+//! configured. Sinks are chosen with `AUDIT_EVENT_SINKS`, a comma-separated
+//! list of `stdout`, `file:<path>` and `https://` URLs, or `http://` ones to
+//! a loopback host (see [`parse_sinks`]).
+
+use std::cell::{Cell, RefCell};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+#[cfg(feature = "server")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "server")]
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Instant;
+#[cfg(feature = "server")]
+use std::time::Duration;
+
+use base64::{Engine as _, engine::general_purpose};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::CryptoError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Success,
+    Failure,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationEvent {
+    /// Always `crypto_operation`, to tell events from logs on a shared stream.
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub timestamp: DateTime<Utc>,
+    pub operation: &'static str,
+    pub key_id: Option<String>,
+    /// Who asked, as established by the transport; e.g. `admin` for a
+    /// request that presented the admin token.
+    pub caller: Option<String>,
+    /// Who the request said it was, e.g. the `x-caller-id` header. Nothing
+    /// checks it, so it must not be relied on.
+    pub claimed_caller: Option<String>,
+    pub request_id: Option<String>,
+    pub outcome: Outcome,
+    /// The [`CryptoError::kind`] of a failure.
+    pub error: Option<&'static str>,
+    pub duration_ms: f64,
+}
+
+/// Who asked for an operation. `id` is an identity the transport verified;
+/// `claimed_id` is one the request merely asserted, e.g. in `x-caller-id`.
+#[derive(Debug, Clone, Default)]
+pub struct Caller {
+    pub id: Option<String>,
+    pub claimed_id: Option<String>,
+    pub request_id: Option<String>,
+}
+
+/// The id logged for a caller-supplied base64 key: the first 16 bytes of
+/// `SHA-256("last-words key id v1" || key)` in hex. Only for random keys;
+/// a password's digest would be open to guessing.
+pub fn key_id(encoded_key: &str) -> Option<String> {
+    let key = Zeroizing::new(general_purpose::STANDARD.decode(encoded_key).ok()?);
+    Some(raw_key_id(&key))
+}
+
+/// [`key_id`] of raw key bytes. X25519 key pairs are logged by their public
+/// half, so sealing to a key and opening with it log the same id.
+pub fn raw_key_id(key: &[u8]) -> String {
+    let digest = Sha256::new().chain_update(b"last-words key id v1").chain_update(key).finalize();
+    hex::encode(&digest[..16])
+}
+
+thread_local! {
+    static CALLER: RefCell<Option<Caller>> = const { RefCell::new(None) };
+    static OBSERVING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Puts the previous caller back, even if the operation panics.
+struct CallerScope(Option<Caller>);
+
+impl Drop for CallerScope {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CALLER.with(|caller| *caller.borrow_mut() = previous);
+    }
+}
+
+/// Puts the previous observing state back, even if the operation panics.
+struct ObservingScope(bool);
+
+impl Drop for ObservingScope {
+    fn drop(&mut self) {
+        OBSERVING.with(|observing| observing.set(self.0));
+    }
+}
+
+/// Runs `op` with its operations' events attributed to `caller`.
+pub fn as_caller<T>(caller: Caller, op: impl FnOnce() -> T) -> T {
+    let _scope = CallerScope(CALLER.with(|current| current.replace(Some(caller))));
+    op()
+}
+
+/// Runs `op` without emitting events for the operations it calls.
+pub fn unobserved<T>(op: impl FnOnce() -> T) -> T {
+    let _scope = ObservingScope(OBSERVING.with(|observing| observing.replace(true)));
+    op()
+}
+
+/// Runs one service operation and emits its event. Operations it calls in
+/// turn emit nothing, so one call is one event.
+pub fn observe<T>(
+    operation: &'static str,
+    key_id: Option<String>,
+    op: impl FnOnce() -> Result<T, CryptoError>,
+) -> Result<T, CryptoError> {
+    // Without sinks there is nothing to time; wasm has no clock to ask.
+    if global().is_empty() || OBSERVING.with(|observing| observing.replace(true)) {
+        return op();
+    }
+    let _scope = ObservingScope(false);
+
+    let started = Instant::now();
+    let result = op();
+    let caller = CALLER.with(|caller| caller.borrow().clone()).unwrap_or_default();
+    global().emit(&OperationEvent {
+        kind: "crypto_operation",
+        timestamp: Utc::now(),
+        operation,
+        key_id,
+        caller: caller.id,
+        claimed_caller: caller.claimed_id,
+        request_id: caller.request_id,
+        outcome: if result.is_ok() { Outcome::Success } else { Outcome::Failure },
+        error: result.as_ref().err().map(CryptoError::kind),
+        duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+    });
+    result
+}
+
+pub trait EventSink: Send + Sync {
+    /// Must not block for long: it runs on the request path.
+    fn emit(&self, event: &OperationEvent);
+}
+
+/// One JSON line per event on stdout.
+pub struct StdoutSink;
+
+impl EventSink for StdoutSink {
+    fn emit(&self, event: &OperationEvent) {
+        if let Ok(line) = serde_json::to_string(event) {
+            println!("{}", line);
+        }
+    }
+}
+
+/// One JSON line per event, appended to a file.
+pub struct FileSink {
+    file: Mutex<File>,
+}
+
+impl FileSink {
+    pub fn open(path: &str) -> Result<Self, CryptoError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| CryptoError::InvalidInput(format!("Cannot open {}: {}", path, e)))?;
+        Ok(Self { file: Mutex::new(file) })
+    }
+}
+
+impl EventSink for FileSink {
+    fn emit(&self, event: &OperationEvent) {
+        let Ok(mut line) = serde_json::to_vec(event) else { return };
+        line.push(b'\n');
+        let written = match self.file.lock() {
+            Ok(mut file) => file.write_all(&line),
+            Err(_) => return,
+        };
+        if let Err(e) = written {
+            tracing::error!(error = %e, "failed to write audit event");
+        }
+    }
+}
+
+/// POSTs each event as JSON to an `http://` or `https://` URL, such as the
+/// API's audit service, from a background thread so a slow receiver never
+/// delays an operation. Events that arrive while the queue is full are
+/// dropped and counted, in [`HttpSink::dropped`] and the metrics.
+#[cfg(feature = "server")]
+pub struct HttpSink {
+    queue: Mutex<SyncSender<OperationEvent>>,
+    dropped: AtomicU64,
+}
+
+#[cfg(feature = "server")]
+impl HttpSink {
+    /// Events waiting for delivery before new ones are dropped.
+    pub const QUEUE_CAPACITY: usize = 1024;
+
+    pub fn new(url: &str) -> Self {
+        Self::with_capacity(url, Self::QUEUE_CAPACITY)
+    }
+
+    pub fn with_capacity(url: &str, capacity: usize) -> Self {
+        let (queue, events) = mpsc::sync_channel::<OperationEvent>(capacity);
+        let url = url.to_string();
+        std::thread::spawn(move || {
+            let client = match reqwest::blocking::Client::builder().timeout(Duration::from_secs(5)).build() {
+                Ok(client) => client,
+                Err(e) => {
+                    tracing::error!(error = %e, "failed to build the audit event HTTP client");
+                    return;
+                }
+            };
+            for event in events {
+                let delivered = client
+                    .post(&url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(serde_json::to_vec(&event).unwrap_or_default())
+                    .send()
+                    .and_then(|response| response.error_for_status());
+                if let Err(e) = delivered {
+                    tracing::error!(error = %e, operation = event.operation, "failed to deliver audit event");
+                }
+            }
+        });
+        Self { queue: Mutex::new(queue), dropped: AtomicU64::new(0) }
+    }
+
+    /// Events dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(feature = "server")]
+impl EventSink for HttpSink {
+    fn emit(&self, event: &OperationEvent) {
+        let Ok(queue) = self.queue.lock() else { return };
+        match queue.try_send(event.clone()) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                crate::metrics::global().event_dropped();
+            }
+            // The delivery thread died; it logged why.
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+fn http_sink(url: &str) -> Result<Arc<dyn EventSink>, CryptoError> {
+    Ok(Arc::new(HttpSink::new(url)))
+}
+
+#[cfg(not(feature = "server"))]
+fn http_sink(url: &str) -> Result<Arc<dyn EventSink>, CryptoError> {
+    Err(CryptoError::InvalidInput(format!("Audit event sink {:?} needs the server feature", url)))
+}
+
+/// Whether the authority at the start of `rest`, a URL after its scheme,
+/// names a loopback host.
+fn is_loopback(rest: &str) -> bool {
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, host_port)| host_port);
+    let host = match host_port.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+        None => host_port.split(':').next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost") || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Parses `AUDIT_EVENT_SINKS`; `none` or an empty list installs no sink.
+pub fn parse_sinks(spec: &str) -> Result<Vec<Arc<dyn EventSink>>, CryptoError> {
+    let mut sinks: Vec<Arc<dyn EventSink>> = Vec::new();
+    for item in spec.split(',').map(str::trim).filter(|item| !item.is_empty() && *item != "none") {
+        if item == "stdout" {
+            sinks.push(Arc::new(StdoutSink));
+        } else if let Some(path) = item.strip_prefix("file:") {
+            sinks.push(Arc::new(FileSink::open(path)?));
+        } else if item.starts_with("https://") {
+            sinks.push(http_sink(item)?);
+        } else if let Some(rest) = item.strip_prefix("http://") {
+            // Events name keys and callers; only loopback may carry them in the clear.
+            if !is_loopback(rest) {
+                return Err(CryptoError::InvalidInput(format!(
+                    "Audit event sink {:?} must use https:// unless its host is loopback",
+                    item
+                )));
+            }
+            sinks.push(http_sink(item)?);
+        } else {
+            return Err(CryptoError::InvalidInput(format!("Unknown audit event sink {:?}", item)));
+        }
+    }
+    Ok(sinks)
+}
+
+/// Installs the sinks `AUDIT_EVENT_SINKS` names in [`global`], or those of
+/// `default` when it is unset, and returns the spec used.
+pub fn install_from_env(default: &str) -> Result<String, CryptoError> {
+    let spec = std::env::var("AUDIT_EVENT_SINKS").unwrap_or_else(|_| default.to_string());
+    for sink in parse_sinks(&spec)? {
+        global().add(sink);
+    }
+    Ok(spec)
+}
+
+#[derive(Default)]
+pub struct Events {
+    sinks: RwLock<Vec<Arc<dyn EventSink>>>,
+}
+
+impl Events {
+    pub fn add(&self, sink: Arc<dyn EventSink>) {
+        if let Ok(mut sinks) = self.sinks.write() {
+            sinks.push(sink);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.read().map(|sinks| sinks.is_empty()).unwrap_or(true)
+    }
+
+    pub fn emit(&self, event: &OperationEvent) {
+        if let Ok(sinks) = self.sinks.read() {
+            for sink in sinks.iter() {
+                sink.emit(event);
+            }
+        }
+    }
+}
+
+/// The process-wide sinks [`observe`] emits to.
+pub fn global() -> &'static Events {
+    static EVENTS: OnceLock<Events> = OnceLock::new();
+    EVENTS.get_or_init(Events::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_sink_writes_json_lines() {
+        let path = std::env::temp_dir().join(format!("audit-events-{}.jsonl", std::process::id()));
+        let spec = format!("file:{}", path.display());
+        let events = Events::default();
+        for sink in parse_sinks(&spec).unwrap() {
+            events.add(sink);
+        }
+
+        let event = OperationEvent {
+            kind: "crypto_operation",
+            timestamp: Utc::now(),
+            operation: "key_unwrap",
+            key_id: key_id("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8="),
+            caller: Some("user-1".to_string()),
+            claimed_caller: None,
+            request_id: None,
+            outcome: Outcome::Failure,
+            error: Some("DecryptionFailed"),
+            duration_ms: 1.5,
+        };
+        events.emit(&event);
+        events.emit(&event);
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<serde_json::Value> = written.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["type"], "crypto_operation");
+        assert_eq!(lines[0]["outcome"], "failure");
+        assert_eq!(lines[0]["key_id"].as_str().unwrap().len(), 32);
+    }
+
+    #[test]
+    fn test_unknown_sink_is_rejected() {
+        assert!(parse_sinks("none").unwrap().is_empty());
+        assert!(parse_sinks("syslog").is_err());
+    }
+
+    #[test]
+    fn test_plaintext_http_sink_must_be_loopback() {
+        for url in ["http://audit.internal/events", "http://127.0.0.1.example/events", "http://localhost@audit.internal/"] {
+            assert!(parse_sinks(url).is_err(), "{}", url);
+        }
+        for rest in ["127.0.0.1:9000/events", "[::1]:9000/events", "localhost/events", "user@127.0.0.1"] {
+            assert!(is_loopback(rest), "{}", rest);
+        }
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_http_sink_drops_events_when_its_queue_is_full() {
+        assert_eq!(parse_sinks("http://127.0.0.1:9000/events, https://audit.example/events").unwrap().len(), 2);
+
+        // Accepts connections but never answers, so delivery stalls.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let sink = HttpSink::with_capacity(&format!("http://{}/events", listener.local_addr().unwrap()), 1);
+        let event = OperationEvent {
+            kind: "crypto_operation",
+            timestamp: Utc::now(),
+            operation: "box_open",
+            key_id: None,
+            caller: None,
+            claimed_caller: None,
+            request_id: None,
+            outcome: Outcome::Success,
+            error: None,
+            duration_ms: 0.5,
+        };
+        for _ in 0..10 {
+            sink.emit(&event);
+        }
+        // One event in flight at most, one queued, the rest dropped.
+        assert!(sink.dropped() >= 8, "dropped {}", sink.dropped());
+    }
+}
@@ -0,0 +1,25 @@
+//! Process hardening applied before the server touches any key material.
+
+use std::io;
+
+/// Disables core dumps and, on Linux, marks the process non-dumpable so other
+/// processes of the same user cannot `ptrace` attach and read its memory.
+#[cfg(unix)]
+pub fn harden_process() -> io::Result<()> {
+    let no_core = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &no_core) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    #[cfg(target_os = "linux")]
+    if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn harden_process() -> io::Result<()> {
+    Ok(())
+}
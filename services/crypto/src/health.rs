@@ -0,0 +1,142 @@
+//! Liveness and readiness for the HTTP server.
+//!
+//! Liveness only says the process answers. Readiness says it can be trusted
+//! with traffic: the known-answer self-tests passed on their last run (at
+//! startup, then every few minutes) and the key store is usable.
+
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::CryptoBoundaryService;
+use crate::selftest::{self, SelfTestResult};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_HASH: &str = env!("GIT_HASH");
+
+#[derive(Debug, Clone, Serialize)]
+struct SelfTestRun {
+    passed: bool,
+    last_run: u64,
+    results: Vec<SelfTestResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct KeyStoreCheck {
+    available: bool,
+    keys: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Checks {
+    self_tests: Option<SelfTestRun>,
+    key_store: KeyStoreCheck,
+}
+
+/// The body of `GET /health/ready`.
+#[derive(Debug, Serialize)]
+pub struct ReadinessReport {
+    #[serde(skip)]
+    pub ready: bool,
+    status: &'static str,
+    version: &'static str,
+    git_hash: &'static str,
+    checks: Checks,
+}
+
+/// The latest self-test results, shared between the periodic runner and the
+/// readiness endpoint.
+#[derive(Debug, Default)]
+pub struct Readiness {
+    self_tests: RwLock<Option<SelfTestRun>>,
+}
+
+impl Readiness {
+    /// Runs the known-answer tests and records the results. Returns whether
+    /// they all passed.
+    pub fn run_self_tests(&self, service: &CryptoBoundaryService) -> bool {
+        let results = selftest::run(service);
+        for failed in results.iter().filter(|result| !result.passed) {
+            tracing::error!(
+                algorithm = failed.algorithm,
+                error = failed.error.as_deref().unwrap_or_default(),
+                "known-answer self-test failed"
+            );
+        }
+
+        let run = SelfTestRun {
+            passed: results.iter().all(|result| result.passed),
+            last_run: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            results,
+        };
+        let passed = run.passed;
+        match self.self_tests.write() {
+            Ok(mut slot) => *slot = Some(run),
+            Err(poisoned) => *poisoned.into_inner() = Some(run),
+        }
+        passed
+    }
+
+    pub fn report(&self, service: &CryptoBoundaryService) -> ReadinessReport {
+        let self_tests = match self.self_tests.read() {
+            Ok(slot) => slot.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        };
+        let key_store = match service.key_store().ids() {
+            Ok(ids) => KeyStoreCheck { available: true, keys: ids.len() },
+            Err(_) => KeyStoreCheck { available: false, keys: 0 },
+        };
+
+        let ready = self_tests.as_ref().is_some_and(|run| run.passed) && key_store.available;
+        ReadinessReport {
+            ready,
+            status: if ready { "ready" } else { "not_ready" },
+            version: VERSION,
+            git_hash: GIT_HASH,
+            checks: Checks { self_tests, key_store },
+        }
+    }
+}
+
+/// The process-wide readiness state the HTTP server reports.
+pub fn global() -> &'static Readiness {
+    static READINESS: OnceLock<Readiness> = OnceLock::new();
+    READINESS.get_or_init(Readiness::default)
+}
+
+/// Re-runs the self-tests every `interval` on the blocking pool.
+pub fn spawn_periodic_self_tests(
+    service: Arc<CryptoBoundaryService>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick fires immediately; startup already ran the tests.
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let service = service.clone();
+            if let Err(e) = tokio::task::spawn_blocking(move || global().run_self_tests(&service)).await {
+                tracing::error!(error = %e, "self-test task panicked");
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_not_ready_until_self_tests_pass() {
+        let service = CryptoBoundaryService::new();
+        let readiness = Readiness::default();
+        assert!(!readiness.report(&service).ready);
+
+        assert!(readiness.run_self_tests(&service));
+        let report = readiness.report(&service);
+        assert!(report.ready);
+        assert_eq!(report.version, env!("CARGO_PKG_VERSION"));
+    }
+}
@@ -0,0 +1,315 @@
+//! Hybrid Public Key Encryption (RFC 9180).
+//!
+//! One fixed suite: DHKEM(X25519, HKDF-SHA256), HKDF-SHA256 and
+//! ChaCha20-Poly1305, in base mode (anonymous sender) or auth mode (the sender
+//! proves possession of its static X25519 key). X25519 keys are the same as
+//! [`crate::sealedbox`] keys, so a beneficiary keypair serves both.
+//!
+//! The suite is built from primitives the crate already uses (`hkdf`, `sha2`,
+//! `chacha20poly1305`) plus `x25519-dalek`, so it is identical with and
+//! without libsodium.
+
+use base64::{Engine as _, engine::general_purpose};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use hkdf::{Hkdf, HkdfExtract};
+use rand::rngs::OsRng;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+pub use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::CryptoError;
+
+pub const KEM_ID: u16 = 0x0020;
+pub const KDF_ID: u16 = 0x0001;
+pub const AEAD_ID: u16 = 0x0003;
+
+/// Length of the encapsulated key, a serialized X25519 public key.
+pub const NENC: usize = 32;
+pub const NPK: usize = 32;
+pub const NSK: usize = 32;
+const NSECRET: usize = 32;
+const NK: usize = 32;
+const NN: usize = 12;
+const NH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Base,
+    Auth,
+}
+
+impl Mode {
+    fn id(self) -> u8 {
+        match self {
+            Mode::Base => 0x00,
+            Mode::Auth => 0x02,
+        }
+    }
+}
+
+fn kem_suite_id() -> [u8; 5] {
+    let [hi, lo] = KEM_ID.to_be_bytes();
+    [b'K', b'E', b'M', hi, lo]
+}
+
+fn hpke_suite_id() -> [u8; 10] {
+    let mut id = *b"HPKE\0\0\0\0\0\0";
+    id[4..6].copy_from_slice(&KEM_ID.to_be_bytes());
+    id[6..8].copy_from_slice(&KDF_ID.to_be_bytes());
+    id[8..10].copy_from_slice(&AEAD_ID.to_be_bytes());
+    id
+}
+
+fn labeled_extract(suite_id: &[u8], salt: &[u8], label: &[u8], ikm: &[&[u8]]) -> Zeroizing<[u8; NH]> {
+    let mut extract = HkdfExtract::<Sha256>::new(Some(salt));
+    extract.input_ikm(b"HPKE-v1");
+    extract.input_ikm(suite_id);
+    extract.input_ikm(label);
+    for part in ikm {
+        extract.input_ikm(part);
+    }
+    let (prk, _) = extract.finalize();
+    Zeroizing::new(prk.into())
+}
+
+fn labeled_expand(
+    prk: &[u8; NH],
+    suite_id: &[u8],
+    label: &[u8],
+    info: &[&[u8]],
+    out: &mut [u8],
+) -> Result<(), CryptoError> {
+    let length = u16::try_from(out.len())
+        .map_err(|_| CryptoError::InvalidInput("HPKE output too long".to_string()))?
+        .to_be_bytes();
+    let mut labeled_info: Vec<&[u8]> = vec![&length, b"HPKE-v1", suite_id, label];
+    labeled_info.extend_from_slice(info);
+    Hkdf::<Sha256>::from_prk(prk)
+        .expect("PRK is Nh bytes")
+        .expand_multi_info(&labeled_info, out)
+        .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))
+}
+
+fn dh(sk: &StaticSecret, pk: &PublicKey) -> Result<Zeroizing<[u8; 32]>, CryptoError> {
+    let shared = sk.diffie_hellman(pk);
+    // An all-zero result means a small-order public key.
+    if !shared.was_contributory() {
+        return Err(CryptoError::InvalidInput("Invalid X25519 public key".to_string()));
+    }
+    Ok(Zeroizing::new(shared.to_bytes()))
+}
+
+fn extract_and_expand(dh: &[&[u8]], kem_context: &[&[u8]]) -> Result<Zeroizing<[u8; NSECRET]>, CryptoError> {
+    let suite_id = kem_suite_id();
+    let eae_prk = labeled_extract(&suite_id, b"", b"eae_prk", dh);
+    let mut shared_secret = Zeroizing::new([0u8; NSECRET]);
+    labeled_expand(&eae_prk, &suite_id, b"shared_secret", kem_context, shared_secret.as_mut())?;
+    Ok(shared_secret)
+}
+
+/// Decodes a base64 X25519 public key.
+pub fn decode_public_key(key: &str) -> Result<PublicKey, CryptoError> {
+    let bytes: [u8; NPK] = general_purpose::STANDARD.decode(key)?
+        .try_into()
+        .map_err(|_| CryptoError::InvalidInput(format!("Public key must be {} bytes", NPK)))?;
+    Ok(PublicKey::from(bytes))
+}
+
+/// Decodes a base64 X25519 secret key.
+pub fn decode_secret_key(key: &str) -> Result<StaticSecret, CryptoError> {
+    let bytes = Zeroizing::new(general_purpose::STANDARD.decode(key)?);
+    let bytes: &[u8; NSK] = bytes.as_slice()
+        .try_into()
+        .map_err(|_| CryptoError::InvalidInput(format!("Secret key must be {} bytes", NSK)))?;
+    Ok(StaticSecret::from(*bytes))
+}
+
+/// Decodes a base64 encapsulated key.
+pub fn decode_enc(enc: &str) -> Result<[u8; NENC], CryptoError> {
+    general_purpose::STANDARD.decode(enc)?
+        .try_into()
+        .map_err(|_| CryptoError::InvalidInput(format!("Encapsulated key must be {} bytes", NENC)))
+}
+
+/// DeriveKeyPair: a deterministic X25519 secret key from input keying material.
+pub fn derive_key_pair(ikm: &[u8]) -> Result<StaticSecret, CryptoError> {
+    let suite_id = kem_suite_id();
+    let dkp_prk = labeled_extract(&suite_id, b"", b"dkp_prk", &[ikm]);
+    let mut sk = Zeroizing::new([0u8; NSK]);
+    labeled_expand(&dkp_prk, &suite_id, b"sk", &[], sk.as_mut())?;
+    Ok(StaticSecret::from(*sk))
+}
+
+fn encap(
+    pk_r: &PublicKey,
+    sender: Option<&StaticSecret>,
+    sk_e: &StaticSecret,
+) -> Result<(Zeroizing<[u8; NSECRET]>, [u8; NENC]), CryptoError> {
+    let enc = PublicKey::from(sk_e).to_bytes();
+    let dh_e = dh(sk_e, pk_r)?;
+    let shared_secret = match sender {
+        None => extract_and_expand(&[dh_e.as_ref()], &[&enc, pk_r.as_bytes()])?,
+        Some(sk_s) => {
+            let dh_s = dh(sk_s, pk_r)?;
+            let pk_s = PublicKey::from(sk_s);
+            extract_and_expand(&[dh_e.as_ref(), dh_s.as_ref()], &[&enc, pk_r.as_bytes(), pk_s.as_bytes()])?
+        }
+    };
+    Ok((shared_secret, enc))
+}
+
+fn decap(
+    enc: &[u8; NENC],
+    sk_r: &StaticSecret,
+    sender: Option<&PublicKey>,
+) -> Result<Zeroizing<[u8; NSECRET]>, CryptoError> {
+    let pk_e = PublicKey::from(*enc);
+    let pk_r = PublicKey::from(sk_r);
+    let dh_e = dh(sk_r, &pk_e)?;
+    match sender {
+        None => extract_and_expand(&[dh_e.as_ref()], &[enc, pk_r.as_bytes()]),
+        Some(pk_s) => {
+            let dh_s = dh(sk_r, pk_s)?;
+            extract_and_expand(&[dh_e.as_ref(), dh_s.as_ref()], &[enc, pk_r.as_bytes(), pk_s.as_bytes()])
+        }
+    }
+}
+
+/// An HPKE encryption context: the AEAD key, base nonce, exporter secret and
+/// message sequence number.
+pub struct Context {
+    key: Zeroizing<[u8; NK]>,
+    base_nonce: [u8; NN],
+    exporter_secret: Zeroizing<[u8; NH]>,
+    seq: u64,
+}
+
+impl Context {
+    fn new(mode: Mode, shared_secret: &[u8; NSECRET], info: &[u8]) -> Result<Self, CryptoError> {
+        // No PSK modes: psk and psk_id are always empty.
+        let suite_id = hpke_suite_id();
+        let psk_id_hash = labeled_extract(&suite_id, b"", b"psk_id_hash", &[]);
+        let info_hash = labeled_extract(&suite_id, b"", b"info_hash", &[info]);
+        let context: [&[u8]; 3] = [&[mode.id()], psk_id_hash.as_ref(), info_hash.as_ref()];
+        let secret = labeled_extract(&suite_id, shared_secret, b"secret", &[]);
+
+        let mut ctx = Self {
+            key: Zeroizing::new([0u8; NK]),
+            base_nonce: [0u8; NN],
+            exporter_secret: Zeroizing::new([0u8; NH]),
+            seq: 0,
+        };
+        labeled_expand(&secret, &suite_id, b"key", &context, ctx.key.as_mut())?;
+        labeled_expand(&secret, &suite_id, b"base_nonce", &context, &mut ctx.base_nonce)?;
+        labeled_expand(&secret, &suite_id, b"exp", &context, ctx.exporter_secret.as_mut())?;
+        Ok(ctx)
+    }
+
+    fn nonce(&self) -> [u8; NN] {
+        let mut nonce = self.base_nonce;
+        for (n, s) in nonce[NN - 8..].iter_mut().zip(self.seq.to_be_bytes()) {
+            *n ^= s;
+        }
+        nonce
+    }
+
+    fn increment_seq(&mut self) -> Result<(), CryptoError> {
+        self.seq = self
+            .seq
+            .checked_add(1)
+            .ok_or_else(|| CryptoError::EncryptionFailed("HPKE message limit reached".to_string()))?;
+        Ok(())
+    }
+
+    pub fn seal(&mut self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let ciphertext = ChaCha20Poly1305::new(self.key.as_ref().into())
+            .encrypt(Nonce::from_slice(&self.nonce()), Payload { msg: plaintext, aad })
+            .map_err(|_| CryptoError::EncryptionFailed("HPKE seal failed".to_string()))?;
+        self.increment_seq()?;
+        Ok(ciphertext)
+    }
+
+    /// A failed open leaves the sequence number unchanged.
+    pub fn open(&mut self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let plaintext = ChaCha20Poly1305::new(self.key.as_ref().into())
+            .decrypt(Nonce::from_slice(&self.nonce()), Payload { msg: ciphertext, aad })
+            .map_err(|_| CryptoError::DecryptionFailed("Failed to open HPKE ciphertext".to_string()))?;
+        self.increment_seq()?;
+        Ok(plaintext)
+    }
+
+    /// The secret export interface: `out.len()` bytes bound to this context.
+    pub fn export(&self, exporter_context: &[u8], out: &mut [u8]) -> Result<(), CryptoError> {
+        labeled_expand(&self.exporter_secret, &hpke_suite_id(), b"sec", &[exporter_context], out)
+    }
+}
+
+/// SetupBaseS / SetupAuthS: auth mode when `sender` is given.
+pub fn setup_sender(
+    pk_r: &PublicKey,
+    info: &[u8],
+    sender: Option<&StaticSecret>,
+) -> Result<([u8; NENC], Context), CryptoError> {
+    setup_sender_with_ephemeral(pk_r, info, sender, &StaticSecret::random_from_rng(OsRng))
+}
+
+/// [`setup_sender`] with a caller-chosen ephemeral key, for known-answer tests
+/// only; reusing an ephemeral key breaks confidentiality.
+pub fn setup_sender_with_ephemeral(
+    pk_r: &PublicKey,
+    info: &[u8],
+    sender: Option<&StaticSecret>,
+    sk_e: &StaticSecret,
+) -> Result<([u8; NENC], Context), CryptoError> {
+    let (shared_secret, enc) = encap(pk_r, sender, sk_e)?;
+    let mode = if sender.is_some() { Mode::Auth } else { Mode::Base };
+    Ok((enc, Context::new(mode, &shared_secret, info)?))
+}
+
+/// SetupBaseR / SetupAuthR: auth mode when `sender` is given.
+pub fn setup_receiver(
+    enc: &[u8; NENC],
+    sk_r: &StaticSecret,
+    info: &[u8],
+    sender: Option<&PublicKey>,
+) -> Result<Context, CryptoError> {
+    let shared_secret = decap(enc, sk_r, sender)?;
+    let mode = if sender.is_some() { Mode::Auth } else { Mode::Base };
+    Context::new(mode, &shared_secret, info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_mode_binds_the_sender() {
+        let sk_r = StaticSecret::random_from_rng(OsRng);
+        let sk_s = StaticSecret::random_from_rng(OsRng);
+        let pk_r = PublicKey::from(&sk_r);
+
+        let (enc, mut sender) = setup_sender(&pk_r, b"info", Some(&sk_s)).unwrap();
+        let first = sender.seal(b"aad", b"first").unwrap();
+        let second = sender.seal(b"aad", b"second").unwrap();
+
+        let mut receiver = setup_receiver(&enc, &sk_r, b"info", Some(&PublicKey::from(&sk_s))).unwrap();
+        assert!(receiver.open(b"other aad", &first).is_err());
+        assert_eq!(receiver.open(b"aad", &first).unwrap(), b"first");
+        assert_eq!(receiver.open(b"aad", &second).unwrap(), b"second");
+
+        // Base mode, another sender or other info derive a different context.
+        let impostor = PublicKey::from(&StaticSecret::random_from_rng(OsRng));
+        for mut context in [
+            setup_receiver(&enc, &sk_r, b"info", None).unwrap(),
+            setup_receiver(&enc, &sk_r, b"info", Some(&impostor)).unwrap(),
+            setup_receiver(&enc, &sk_r, b"other", Some(&PublicKey::from(&sk_s))).unwrap(),
+        ] {
+            assert!(context.open(b"aad", &first).is_err());
+        }
+
+        // Small-order points are rejected.
+        assert!(setup_sender(&PublicKey::from([0u8; 32]), b"", None).is_err());
+    }
+}
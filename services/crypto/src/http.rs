@@ -0,0 +1,778 @@
+//! HTTP transport for [`CryptoBoundaryService`].
+
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Instant;
+
+use secrecy::ExposeSecret;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use subtle::ConstantTimeEq;
+use tracing::Span;
+use warp::filters::body::BodyDeserializeError;
+use warp::filters::cors::CorsForbidden;
+use warp::filters::{log, trace};
+use warp::http::StatusCode;
+use warp::{Filter, Rejection, reject};
+
+use crate::events::{self, Caller};
+use crate::{Secret, health, metrics, telemetry};
+use crate::{
+    AeadDecryptRequest, AeadEncryptRequest, AgeDecryptRequest, AgeEncryptRequest,
+    AuditConsistencyProofRequest, AuditEvent, AuditInclusionProofRequest, AuditVerifyRequest,
+    BoxOpenRequest, BoxSealRequest, ConfirmAliveCheckRequest, CryptoBoundaryService, CryptoError,
+    EnvelopeAddRecipientsRequest, EnvelopeOpenRequest, EnvelopeRemoveRecipientsRequest, EnvelopeSealRequest,
+    EvaluateAliveChecksRequest, HpkeOpenRequest, HpkeSealRequest, KdfRequest, KeyUnwrapRequest,
+    KeyWrapRequest, ReleaseArchiveRequest, ReleaseCertificateRequest, SignRequest,
+    VerifyAuditInclusionRequest, VerifyReleaseCertificateRequest, VerifyRequest,
+};
+
+/// Who asked, from the `x-caller-id` and `x-request-id` headers. Anyone can
+/// set those, so the caller is only claimed, never verified.
+fn caller() -> impl Filter<Extract = (Caller,), Error = Infallible> + Clone {
+    warp::header::headers_cloned().map(|headers: warp::http::HeaderMap| {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).map(str::to_string);
+        Caller { id: None, claimed_id: header("x-caller-id"), request_id: header("x-request-id") }
+    })
+}
+
+/// The caller on routes behind [`admin`]: the bearer token proved it is the
+/// admin, whatever `x-caller-id` claims.
+fn admin_caller() -> impl Filter<Extract = (Caller,), Error = Infallible> + Clone {
+    caller().map(|caller: Caller| Caller { id: Some("admin".to_string()), ..caller })
+}
+
+/// Runs a service operation on behalf of `caller`, whose audit event the
+/// service emits, and turns its result into the JSON reply, recording the
+/// outcome on the request span and in the metrics. Errors are reported in the
+/// body with status 200, as the API expects.
+fn respond<T: Serialize>(
+    operation: &'static str,
+    caller: Caller,
+    op: impl FnOnce() -> Result<T, CryptoError>,
+) -> Result<warp::reply::Json, Infallible> {
+    let started = Instant::now();
+    let result = events::as_caller(caller, op);
+    metrics::global().observe(operation, started.elapsed(), &result);
+
+    let span = Span::current();
+    span.record("operation", operation);
+    match result {
+        Ok(response) => {
+            span.record("outcome", "ok");
+            Ok(warp::reply::json(&response))
+        }
+        Err(e) => {
+            span.record("outcome", "error");
+            tracing::warn!(operation, kind = e.kind(), error = %e, "crypto operation failed");
+            Ok(warp::reply::json(&serde_json::json!({
+                "error": e.to_string()
+            })))
+        }
+    }
+}
+
+// HTTP handlers
+async fn kdf_handler(
+    req: KdfRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("kdf", caller, || metrics::global().time_kdf(|| service.kdf_argon2id(req)))
+}
+
+async fn aead_encrypt_handler(
+    req: AeadEncryptRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("aead_encrypt", caller, || service.aead_encrypt(req))
+}
+
+async fn aead_decrypt_handler(
+    req: AeadDecryptRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("aead_decrypt", caller, || service.aead_decrypt(req))
+}
+
+async fn key_wrap_handler(
+    req: KeyWrapRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("key_wrap", caller, || service.key_wrap(req))
+}
+
+async fn key_unwrap_handler(
+    req: KeyUnwrapRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("key_unwrap", caller, || service.key_unwrap(req))
+}
+
+async fn box_keypair_handler(
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("box_keypair", caller, || service.box_keypair())
+}
+
+async fn box_seal_handler(
+    req: BoxSealRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("box_seal", caller, || service.box_seal(req))
+}
+
+async fn box_open_handler(
+    req: BoxOpenRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("box_open", caller, || service.box_open(req))
+}
+
+async fn hpke_seal_handler(
+    req: HpkeSealRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("hpke_seal", caller, || service.hpke_seal(req))
+}
+
+async fn hpke_open_handler(
+    req: HpkeOpenRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("hpke_open", caller, || service.hpke_open(req))
+}
+
+async fn envelope_seal_handler(
+    req: EnvelopeSealRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("envelope_seal", caller, || service.envelope_seal(req))
+}
+
+async fn envelope_open_handler(
+    req: EnvelopeOpenRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("envelope_open", caller, || service.envelope_open(req))
+}
+
+async fn envelope_add_recipients_handler(
+    req: EnvelopeAddRecipientsRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("envelope_add_recipients", caller, || service.envelope_add_recipients(req))
+}
+
+async fn envelope_remove_recipients_handler(
+    req: EnvelopeRemoveRecipientsRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("envelope_remove_recipients", caller, || service.envelope_remove_recipients(req))
+}
+
+async fn age_keygen_handler(
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("age_keygen", caller, || service.age_keygen())
+}
+
+async fn age_encrypt_handler(
+    req: AgeEncryptRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("age_encrypt", caller, || service.age_encrypt(req))
+}
+
+async fn age_decrypt_handler(
+    req: AgeDecryptRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("age_decrypt", caller, || service.age_decrypt(req))
+}
+
+async fn signing_keys_handler(
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("signing_keys", caller, || service.signing_keys())
+}
+
+async fn sign_handler(
+    req: SignRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("sign", caller, || service.sign(req))
+}
+
+async fn verify_handler(
+    req: VerifyRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("verify", caller, || service.verify(req))
+}
+
+async fn issue_release_certificate_handler(
+    req: ReleaseCertificateRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("release_certificate", caller, || service.issue_release_certificate(req))
+}
+
+async fn export_release_archive_handler(
+    req: ReleaseArchiveRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("release_archive", caller, || service.export_release_archive(req))
+}
+
+async fn verify_release_certificate_handler(
+    req: VerifyReleaseCertificateRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("release_certificate_verify", caller, || service.verify_release_certificate(req))
+}
+
+async fn audit_append_handler(
+    event: AuditEvent,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("audit_append", caller, || service.audit_append(event))
+}
+
+async fn audit_log_handler(
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("audit_log", caller, || service.audit_log())
+}
+
+async fn audit_verify_handler(
+    req: AuditVerifyRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("audit_verify", caller, || service.verify_audit_chain(req))
+}
+
+async fn audit_checkpoint_handler(
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("audit_checkpoint", caller, || service.audit_checkpoint())
+}
+
+async fn audit_tree_head_handler(
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("audit_tree_head", caller, || service.audit_tree_head())
+}
+
+async fn audit_inclusion_proof_handler(
+    req: AuditInclusionProofRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("audit_inclusion_proof", caller, || service.audit_inclusion_proof(req))
+}
+
+async fn audit_consistency_proof_handler(
+    req: AuditConsistencyProofRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("audit_consistency_proof", caller, || service.audit_consistency_proof(req))
+}
+
+async fn audit_verify_inclusion_handler(
+    req: VerifyAuditInclusionRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("audit_verify_inclusion", caller, || service.verify_audit_inclusion(req))
+}
+
+async fn evaluate_alive_checks_handler(
+    req: EvaluateAliveChecksRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("alive_checks_evaluate", caller, || service.evaluate_alive_checks(req))
+}
+
+async fn confirm_alive_check_handler(
+    req: ConfirmAliveCheckRequest,
+    service: Arc<CryptoBoundaryService>,
+    caller: Caller,
+) -> Result<impl warp::Reply, Infallible> {
+    respond("alive_checks_confirm", caller, || service.confirm_alive_check(req))
+}
+
+/// Opens the `request` span, continuing the caller's request id and trace.
+fn request_span(info: trace::Info<'_>) -> Span {
+    let headers = info.request_headers();
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+    let request_id = header("x-request-id")
+        .map(str::to_string)
+        .unwrap_or_else(telemetry::new_request_id);
+    let parent = header("traceparent").and_then(telemetry::parse_traceparent);
+
+    let span = tracing::info_span!(
+        "request",
+        method = %info.method(),
+        path = info.path(),
+        request_id = %request_id,
+        trace_id = parent.as_ref().map(|p| p.trace_id.as_str()),
+        parent_id = parent.as_ref().map(|p| p.parent_id.as_str()),
+        operation = tracing::field::Empty,
+        outcome = tracing::field::Empty,
+    );
+    telemetry::set_remote_parent(&span, headers);
+    span
+}
+
+/// Emits one event per completed request with its status and latency.
+fn log_completion(info: log::Info<'_>) {
+    tracing::info!(
+        status = info.status().as_u16(),
+        latency_ms = info.elapsed().as_secs_f64() * 1000.0,
+        "request completed"
+    );
+}
+
+/// Largest request body accepted, in bytes; larger bodies get 413.
+pub const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+/// A request to an admin route without the right bearer token.
+#[derive(Debug)]
+struct Unauthorized;
+
+impl reject::Reject for Unauthorized {}
+
+/// Passes only requests carrying `Authorization: Bearer <admin_token>`. With
+/// no token configured, admin routes refuse everything.
+fn admin(admin_token: Option<Arc<Secret>>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |header: Option<String>| {
+            let admin_token = admin_token.clone();
+            async move {
+                let presented = header.as_deref().and_then(|value| value.strip_prefix("Bearer "));
+                match (admin_token, presented) {
+                    (Some(expected), Some(presented))
+                        if bool::from(expected.expose_secret().as_bytes().ct_eq(presented.as_bytes())) =>
+                    {
+                        Ok(())
+                    }
+                    _ => Err(reject::custom(Unauthorized)),
+                }
+            }
+        })
+        .untuple_one()
+}
+
+fn json_body<T: DeserializeOwned + Send>() -> impl Filter<Extract = (T,), Error = Rejection> + Clone {
+    warp::body::content_length_limit(MAX_BODY_BYTES).and(warp::body::json())
+}
+
+/// Turns filter rejections into JSON errors with a matching status code.
+async fn handle_rejection(err: Rejection) -> Result<impl warp::Reply, Infallible> {
+    let (status, message) = if err.is_not_found() {
+        (StatusCode::NOT_FOUND, "Not found".to_string())
+    } else if err.find::<Unauthorized>().is_some() {
+        (StatusCode::UNAUTHORIZED, "Missing or invalid admin token".to_string())
+    } else if let Some(e) = err.find::<BodyDeserializeError>() {
+        // serde's text can quote the body, secrets included; only its position goes back.
+        tracing::debug!(error = %e, "malformed JSON body");
+        let position = std::error::Error::source(e)
+            .and_then(|cause| cause.downcast_ref::<serde_json::Error>())
+            .map(|e| format!(" at line {}, column {}", e.line(), e.column()))
+            .unwrap_or_default();
+        (StatusCode::BAD_REQUEST, format!("Malformed JSON body{}", position))
+    } else if err.find::<reject::PayloadTooLarge>().is_some() {
+        (StatusCode::PAYLOAD_TOO_LARGE, format!("Request body exceeds {} bytes", MAX_BODY_BYTES))
+    } else if err.find::<reject::LengthRequired>().is_some() {
+        (StatusCode::LENGTH_REQUIRED, "Content-Length is required".to_string())
+    } else if err.find::<reject::UnsupportedMediaType>().is_some() {
+        (StatusCode::UNSUPPORTED_MEDIA_TYPE, "Expected application/json".to_string())
+    } else if err.find::<reject::MethodNotAllowed>().is_some() {
+        (StatusCode::METHOD_NOT_ALLOWED, "Method not allowed".to_string())
+    } else if let Some(e) = err.find::<CorsForbidden>() {
+        (StatusCode::FORBIDDEN, e.to_string())
+    } else {
+        tracing::error!(rejection = ?err, "unhandled rejection");
+        (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
+    };
+
+    Span::current().record("outcome", "rejected");
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "error": message })),
+        status,
+    ))
+}
+
+/// Builds the complete route tree, CORS and request tracing included.
+/// Operations that add to what the service signs or records (signing,
+/// release certificates, the audit log, the dead man's switch), reading the
+/// audit log and archive export require `admin_token` as a bearer token;
+/// the signing key itself is only ever changed by restarting with a new
+/// `SIGNING_KEY_FILE`.
+pub fn routes(
+    service: Arc<CryptoBoundaryService>,
+    admin_token: Option<Secret>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Infallible> + Clone {
+    // CORS configuration
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_headers(vec![
+            "content-type", "authorization", "x-request-id", "x-caller-id", "traceparent", "tracestate",
+        ])
+        .allow_methods(vec!["POST", "GET", "OPTIONS"]);
+    
+    // Routes
+    let service_filter = warp::any().map(move || service.clone());
+    let admin = admin(admin_token.map(Arc::new));
+    
+    let kdf_route = warp::path!("kdf" / "argon2id")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(kdf_handler);
+    
+    let aead_encrypt_route = warp::path!("aead" / "encrypt")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(aead_encrypt_handler);
+    
+    let aead_decrypt_route = warp::path!("aead" / "decrypt")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(aead_decrypt_handler);
+    
+    let key_wrap_route = warp::path!("key" / "wrap")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(key_wrap_handler);
+    
+    let key_unwrap_route = warp::path!("key" / "unwrap")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(key_unwrap_handler);
+    
+    let box_keypair_route = warp::path!("box" / "keypair")
+        .and(warp::post())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(box_keypair_handler);
+    
+    let box_seal_route = warp::path!("box" / "seal")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(box_seal_handler);
+    
+    let box_open_route = warp::path!("box" / "open")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(box_open_handler);
+    
+    let hpke_seal_route = warp::path!("hpke" / "seal")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(hpke_seal_handler);
+    
+    let hpke_open_route = warp::path!("hpke" / "open")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(hpke_open_handler);
+    
+    let envelope_seal_route = warp::path!("envelope" / "seal")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(envelope_seal_handler);
+    
+    let envelope_open_route = warp::path!("envelope" / "open")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(envelope_open_handler);
+    
+    let envelope_add_recipients_route = warp::path!("envelope" / "recipients" / "add")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(envelope_add_recipients_handler);
+    
+    let envelope_remove_recipients_route = warp::path!("envelope" / "recipients" / "remove")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(envelope_remove_recipients_handler);
+    
+    let age_keygen_route = warp::path!("age" / "keygen")
+        .and(warp::post())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(age_keygen_handler);
+    
+    let age_encrypt_route = warp::path!("age" / "encrypt")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(age_encrypt_handler);
+    
+    let age_decrypt_route = warp::path!("age" / "decrypt")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(age_decrypt_handler);
+    
+    // Public: verifiers fetch the JWK Set without any secret.
+    let signing_keys_route = warp::path!("keys" / "signing")
+        .and(warp::get())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(signing_keys_handler);
+    
+    let sign_route = warp::path!("sign")
+        .and(warp::post())
+        .and(admin.clone())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(admin_caller())
+        .and_then(sign_handler);
+    
+    let verify_route = warp::path!("verify")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(verify_handler);
+    
+    let issue_release_certificate_route = warp::path!("release" / "certificate")
+        .and(warp::post())
+        .and(admin.clone())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(admin_caller())
+        .and_then(issue_release_certificate_handler);
+    
+    let verify_release_certificate_route = warp::path!("release" / "certificate" / "verify")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(verify_release_certificate_handler);
+    
+    let export_release_archive_route = warp::path!("release" / "archive")
+        .and(warp::post())
+        .and(admin.clone())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(admin_caller())
+        .and_then(export_release_archive_handler);
+    
+    let audit_append_route = warp::path!("audit" / "append")
+        .and(warp::post())
+        .and(admin.clone())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(admin_caller())
+        .and_then(audit_append_handler);
+    
+    // Records name users and carry details; verifiers without the token use
+    // the signed tree head and proofs instead.
+    let audit_log_route = warp::path!("audit" / "log")
+        .and(warp::get())
+        .and(admin.clone())
+        .and(service_filter.clone())
+        .and(admin_caller())
+        .and_then(audit_log_handler);
+    
+    let audit_verify_route = warp::path!("audit" / "verify")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(audit_verify_handler);
+    
+    let audit_checkpoint_route = warp::path!("audit" / "checkpoint")
+        .and(warp::post())
+        .and(admin.clone())
+        .and(service_filter.clone())
+        .and(admin_caller())
+        .and_then(audit_checkpoint_handler);
+    
+    let audit_tree_head_route = warp::path!("audit" / "tree-head")
+        .and(warp::get())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(audit_tree_head_handler);
+    
+    let audit_inclusion_proof_route = warp::path!("audit" / "proof" / "inclusion")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(audit_inclusion_proof_handler);
+    
+    let audit_consistency_proof_route = warp::path!("audit" / "proof" / "consistency")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(audit_consistency_proof_handler);
+    
+    let audit_verify_inclusion_route = warp::path!("audit" / "verify" / "inclusion")
+        .and(warp::post())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(caller())
+        .and_then(audit_verify_inclusion_handler);
+    
+    let evaluate_alive_checks_route = warp::path!("alive-checks" / "evaluate")
+        .and(warp::post())
+        .and(admin.clone())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(admin_caller())
+        .and_then(evaluate_alive_checks_handler);
+    
+    let confirm_alive_check_route = warp::path!("alive-checks" / "confirm")
+        .and(warp::post())
+        .and(admin.clone())
+        .and(json_body())
+        .and(service_filter.clone())
+        .and(admin_caller())
+        .and_then(confirm_alive_check_handler);
+    
+    // Plain /health is kept for existing probes and behaves like /health/live.
+    let live_route = warp::path!("health")
+        .or(warp::path!("health" / "live"))
+        .unify()
+        .and(warp::get())
+        .map(|| warp::reply::json(&serde_json::json!({
+            "status": "healthy",
+            "service": "crypto-boundary",
+            "version": health::VERSION,
+            "git_hash": health::GIT_HASH
+        })));
+    
+    let ready_route = warp::path!("health" / "ready")
+        .and(warp::get())
+        .and(service_filter.clone())
+        .map(|service: Arc<CryptoBoundaryService>| {
+            let report = health::global().report(&service);
+            let status = if report.ready {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+            warp::reply::with_status(warp::reply::json(&report), status)
+        });
+    
+    let metrics_route = warp::path!("metrics")
+        .and(warp::get())
+        .map(|| warp::reply::with_header(
+            metrics::global().render(),
+            "content-type",
+            prometheus::TEXT_FORMAT,
+        ));
+    
+    // Grouped so the nesting of the combined filter type stays shallow
+    // enough for the compiler's query depth limit.
+    let signing_routes = signing_keys_route
+        .or(sign_route)
+        .or(verify_route)
+        .or(issue_release_certificate_route)
+        .or(verify_release_certificate_route)
+        .or(export_release_archive_route);
+    
+    let audit_routes = audit_append_route
+        .or(audit_log_route)
+        .or(audit_verify_route)
+        .or(audit_checkpoint_route)
+        .or(audit_tree_head_route)
+        .or(audit_inclusion_proof_route)
+        .or(audit_consistency_proof_route)
+        .or(audit_verify_inclusion_route);
+    
+    let alive_check_routes = evaluate_alive_checks_route.or(confirm_alive_check_route);
+    
+    kdf_route
+        .or(aead_encrypt_route)
+        .or(aead_decrypt_route)
+        .or(key_wrap_route)
+        .or(key_unwrap_route)
+        .or(box_keypair_route)
+        .or(box_seal_route)
+        .or(box_open_route)
+        .or(hpke_seal_route)
+        .or(hpke_open_route)
+        .or(envelope_seal_route)
+        .or(envelope_open_route)
+        .or(envelope_add_recipients_route)
+        .or(envelope_remove_recipients_route)
+        .or(age_keygen_route)
+        .or(age_encrypt_route)
+        .or(age_decrypt_route)
+        .or(signing_routes)
+        .or(audit_routes)
+        .or(alive_check_routes)
+        .or(live_route)
+        .or(ready_route)
+        .or(metrics_route)
+        // Recover inside CORS so error bodies carry CORS headers, and again
+        // outside it for rejected preflights.
+        .recover(handle_rejection)
+        .with(cors)
+        .recover(handle_rejection)
+        .with(log::custom(log_completion))
+        .with(trace::trace(request_span))
+}
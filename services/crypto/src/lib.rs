@@ -0,0 +1,67 @@
+//! Last Words crypto boundary.
+//!
+//! The crate root is the stable public API: [`CryptoBoundaryService`], its
+//! request and response types, and [`CryptoError`]. The HTTP server, the CLIs
+//! and any bindings are thin layers over the same service.
+
+mod error;
+mod service;
+mod types;
+
+pub mod aead;
+pub mod age;
+pub mod archive;
+pub mod audit;
+pub mod auditlog;
+pub mod canonical;
+pub mod certificate;
+pub mod deadman;
+pub mod events;
+#[cfg(feature = "server")]
+pub mod hardening;
+#[cfg(feature = "server")]
+pub mod health;
+pub mod hpke;
+#[cfg(feature = "server")]
+pub mod http;
+pub mod merkle;
+#[cfg(feature = "server")]
+pub mod metrics;
+#[cfg(feature = "node")]
+pub mod node;
+pub mod recipients;
+pub mod redact;
+pub mod sealedbox;
+pub mod secure;
+pub mod selftest;
+pub mod shamir;
+pub mod signing;
+#[cfg(feature = "server")]
+pub mod telemetry;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use error::CryptoError;
+pub use redact::Secret;
+pub use service::CryptoBoundaryService;
+pub use types::{
+    AeadDecryptRequest, AeadDecryptResponse, AeadEncryptRequest, AeadEncryptResponse,
+    AgeDecryptRequest, AgeDecryptResponse, AgeEncryptRequest, AgeEncryptResponse, AgeIdentity,
+    AgeKeygenResponse, AgeRecipient, AliveCheck, AliveCheckEvent, ArchiveItem, ArchiveProtection,
+    AuditAppendResponse, AuditChainBreak, AuditChainBreakReason, AuditConsistencyProofRequest,
+    AuditConsistencyProofResponse, AuditEvent, AuditInclusionProofRequest,
+    AuditInclusionProofResponse, AuditLogResponse, AuditRecord, AuditTreeHead, AuditVerifyRequest,
+    AuditVerifyResponse, BoxKeypairResponse, BoxOpenRequest, BoxOpenResponse, BoxSealRequest,
+    BoxSealResponse, ConfirmAliveCheckRequest, ConfirmAliveCheckResponse,
+    EnvelopeAddRecipientsRequest, EnvelopeOpenRequest, EnvelopeOpenResponse,
+    EnvelopeRemoveRecipientsRequest, EnvelopeSealRequest, EvaluateAliveChecksRequest,
+    EvaluateAliveChecksResponse, HpkeOpenRequest, HpkeOpenResponse, HpkeSealRequest,
+    HpkeSealResponse, Jwk, KdfRequest, KdfResponse, KeyUnwrapRequest, KeyUnwrapResponse,
+    KeyWrapRequest, KeyWrapResponse, MultiRecipientEnvelope, Recipient, RecipientIdentity,
+    RecipientStanza, ReleaseApproval, ReleaseArchiveRequest, ReleaseArchiveResponse,
+    ReleaseAttestation, ReleaseCertificate, ReleaseCertificateRequest, ReleaseEventType,
+    ShamirCombineRequest, ShamirCombineResponse, ShamirSplitRequest, ShamirSplitResponse,
+    SignRequest, SignResponse, SignedTreeHead, SigningKeysResponse, VerifyAuditInclusionRequest,
+    VerifyAuditInclusionResponse, VerifyReleaseCertificateRequest, VerifyReleaseCertificateResponse,
+    VerifyRequest, VerifyResponse,
+};
@@ -0,0 +1,170 @@
+//! Prometheus metrics for the HTTP server, served at `GET /metrics`.
+//!
+//! Everything lives in one private registry under the `crypto_boundary`
+//! namespace, plus the standard `process_*` collector on Linux. Labels never
+//! carry request data: endpoints are fixed operation names and errors are
+//! labelled by [`CryptoError::kind`].
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+use crate::{CryptoError, KdfResponse};
+
+const NAMESPACE: &str = "crypto_boundary";
+
+// Argon2id at production settings takes hundreds of milliseconds to seconds.
+const KDF_BUCKETS: &[f64] = &[0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    errors: IntCounterVec,
+    latency: HistogramVec,
+    kdf_duration: HistogramVec,
+    kdf_in_flight: IntGauge,
+    events_dropped: IntCounter,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let requests = IntCounterVec::new(
+            Opts::new("requests_total", "Crypto operations handled, by endpoint").namespace(NAMESPACE),
+            &["endpoint"],
+        ).expect("valid metric");
+        let errors = IntCounterVec::new(
+            Opts::new("errors_total", "Failed crypto operations, by endpoint and CryptoError variant")
+                .namespace(NAMESPACE),
+            &["endpoint", "kind"],
+        ).expect("valid metric");
+        let latency = HistogramVec::new(
+            HistogramOpts::new("request_duration_seconds", "Crypto operation latency, by endpoint")
+                .namespace(NAMESPACE),
+            &["endpoint"],
+        ).expect("valid metric");
+        let kdf_duration = HistogramVec::new(
+            HistogramOpts::new("kdf_duration_seconds", "Argon2id duration, by memory cost in KiB")
+                .namespace(NAMESPACE)
+                .buckets(KDF_BUCKETS.to_vec()),
+            &["memory_kib"],
+        ).expect("valid metric");
+        let kdf_in_flight = IntGauge::with_opts(
+            Opts::new("kdf_in_flight", "Argon2id derivations currently running").namespace(NAMESPACE),
+        ).expect("valid metric");
+        let events_dropped = IntCounter::with_opts(
+            Opts::new("audit_events_dropped_total", "Audit events dropped because an HTTP sink's queue was full")
+                .namespace(NAMESPACE),
+        ).expect("valid metric");
+
+        registry.register(Box::new(requests.clone())).expect("unique metric");
+        registry.register(Box::new(errors.clone())).expect("unique metric");
+        registry.register(Box::new(latency.clone())).expect("unique metric");
+        registry.register(Box::new(kdf_duration.clone())).expect("unique metric");
+        registry.register(Box::new(kdf_in_flight.clone())).expect("unique metric");
+        registry.register(Box::new(events_dropped.clone())).expect("unique metric");
+
+        // process_resident_memory_bytes, process_cpu_seconds_total, open fds, ...
+        #[cfg(target_os = "linux")]
+        registry
+            .register(Box::new(prometheus::process_collector::ProcessCollector::for_self()))
+            .expect("unique metric");
+
+        Self { registry, requests, errors, latency, kdf_duration, kdf_in_flight, events_dropped }
+    }
+
+    /// Records one completed operation on `endpoint`.
+    pub fn observe<T>(&self, endpoint: &str, elapsed: Duration, result: &Result<T, CryptoError>) {
+        self.requests.with_label_values(&[endpoint]).inc();
+        self.latency.with_label_values(&[endpoint]).observe(elapsed.as_secs_f64());
+        if let Err(e) = result {
+            self.errors.with_label_values(&[endpoint, e.kind()]).inc();
+        }
+    }
+
+    pub fn event_dropped(&self) {
+        self.events_dropped.inc();
+    }
+
+    /// Runs an Argon2id derivation, tracking it in the in-flight gauge and,
+    /// on success, in the duration histogram for its memory cost.
+    pub fn time_kdf(
+        &self,
+        kdf: impl FnOnce() -> Result<KdfResponse, CryptoError>,
+    ) -> Result<KdfResponse, CryptoError> {
+        self.kdf_in_flight.inc();
+        let started = Instant::now();
+        let result = kdf();
+        let elapsed = started.elapsed();
+        self.kdf_in_flight.dec();
+
+        if let Ok(response) = &result {
+            // Callers choose the memory cost; only powers of two get their own
+            // series so a client cannot blow up the label cardinality.
+            let memory = if response.memory.is_power_of_two() {
+                response.memory.to_string()
+            } else {
+                "other".to_string()
+            };
+            self.kdf_duration.with_label_values(&[&memory]).observe(elapsed.as_secs_f64());
+        }
+        result
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!(error = %e, "failed to encode metrics");
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+/// The process-wide metrics the HTTP server records into.
+pub fn global() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CryptoBoundaryService, KdfRequest};
+
+    #[test]
+    fn test_render_counts_errors_by_kind_and_kdf_by_memory() {
+        let metrics = Metrics::new();
+        let service = CryptoBoundaryService::new();
+
+        let ok = metrics.time_kdf(|| service.kdf_argon2id(KdfRequest {
+            password: "password".into(),
+            salt: None,
+            memory: Some(4096),
+            iterations: Some(1),
+            parallelism: Some(1),
+        }));
+        metrics.observe("kdf", Duration::from_millis(5), &ok);
+        let err: Result<(), _> = Err(CryptoError::DecryptionFailed("tag mismatch".to_string()));
+        metrics.observe("aead_decrypt", Duration::from_millis(1), &err);
+
+        let text = metrics.render();
+        assert!(text.contains(r#"crypto_boundary_requests_total{endpoint="kdf"} 1"#));
+        assert!(text.contains(r#"crypto_boundary_errors_total{endpoint="aead_decrypt",kind="DecryptionFailed"} 1"#));
+        assert!(text.contains(r#"crypto_boundary_kdf_duration_seconds_count{memory_kib="4096"} 1"#));
+        assert!(text.contains("crypto_boundary_kdf_in_flight 0"));
+        assert!(!text.contains("tag mismatch"));
+        #[cfg(target_os = "linux")]
+        assert!(text.contains("process_resident_memory_bytes"));
+    }
+}
@@ -0,0 +1,193 @@
+//! N-API bindings for the NestJS API.
+//!
+//! Every export takes a plain object shaped like the HTTP request body and
+//! returns a Promise of the response body. The work runs as an `AsyncTask` on
+//! libuv's threadpool, so Argon2id and friends never block the event loop.
+//!
+//! The addon has its own `CryptoBoundaryService`, without the server's
+//! signing key or audit log. Signing, the audit log and the dead man's switch
+//! stay behind the HTTP server; the addon only verifies what they produce,
+//! given the signer's `publicKey`. Its operations are reported to the
+//! `AUDIT_EVENT_SINKS` sinks, if set; `http(s)://` sinks need the server build.
+
+use std::sync::OnceLock;
+
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, JsUnknown, Task};
+use napi_derive::napi;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{CryptoBoundaryService, CryptoError, events};
+
+type Job = Box<dyn FnOnce(&CryptoBoundaryService) -> Result<Value, CryptoError> + Send>;
+
+fn service() -> &'static CryptoBoundaryService {
+    static SERVICE: OnceLock<CryptoBoundaryService> = OnceLock::new();
+    SERVICE.get_or_init(CryptoBoundaryService::new)
+}
+
+/// Installs the `AUDIT_EVENT_SINKS` sinks on first use.
+fn install_sinks() -> napi::Result<()> {
+    static INSTALLED: OnceLock<Result<(), String>> = OnceLock::new();
+    INSTALLED
+        .get_or_init(|| events::install_from_env("none").map(drop).map_err(|e| e.to_string()))
+        .clone()
+        .map_err(napi::Error::from_reason)
+}
+
+pub struct ServiceTask {
+    job: Option<Job>,
+}
+
+impl Task for ServiceTask {
+    type Output = Value;
+    type JsValue = JsUnknown;
+
+    fn compute(&mut self) -> napi::Result<Value> {
+        let job = self
+            .job
+            .take()
+            .ok_or_else(|| napi::Error::from_reason("Task already run"))?;
+        install_sinks()?;
+        job(service()).map_err(|e| napi::Error::from_reason(e.to_string()))
+    }
+
+    fn resolve(&mut self, env: Env, output: Value) -> napi::Result<JsUnknown> {
+        env.to_js_value(&output)
+    }
+}
+
+fn task<Req, Res>(
+    req: Value,
+    op: fn(&CryptoBoundaryService, Req) -> Result<Res, CryptoError>,
+) -> AsyncTask<ServiceTask>
+where
+    Req: DeserializeOwned + 'static,
+    Res: Serialize + 'static,
+{
+    AsyncTask::new(ServiceTask {
+        job: Some(Box::new(move |service| {
+            let req = serde_json::from_value(req)
+                .map_err(|e| CryptoError::InvalidInput(e.to_string()))?;
+            let res = op(service, req)?;
+            serde_json::to_value(res).map_err(|e| CryptoError::InvalidInput(e.to_string()))
+        })),
+    })
+}
+
+#[napi(js_name = "kdfArgon2id", ts_return_type = "Promise<KdfResponse>")]
+pub fn kdf_argon2id(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::kdf_argon2id)
+}
+
+#[napi(ts_return_type = "Promise<AeadEncryptResponse>")]
+pub fn aead_encrypt(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::aead_encrypt)
+}
+
+#[napi(ts_return_type = "Promise<AeadDecryptResponse>")]
+pub fn aead_decrypt(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::aead_decrypt)
+}
+
+#[napi(ts_return_type = "Promise<KeyWrapResponse>")]
+pub fn key_wrap(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::key_wrap)
+}
+
+#[napi(ts_return_type = "Promise<KeyUnwrapResponse>")]
+pub fn key_unwrap(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::key_unwrap)
+}
+
+#[napi(ts_return_type = "Promise<ShamirSplitResponse>")]
+pub fn shamir_split(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::shamir_split)
+}
+
+#[napi(ts_return_type = "Promise<ShamirCombineResponse>")]
+pub fn shamir_combine(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::shamir_combine)
+}
+
+#[napi(ts_return_type = "Promise<BoxKeypairResponse>")]
+pub fn box_keypair() -> AsyncTask<ServiceTask> {
+    task(Value::Null, |service, ()| service.box_keypair())
+}
+
+#[napi(ts_return_type = "Promise<BoxSealResponse>")]
+pub fn box_seal(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::box_seal)
+}
+
+#[napi(ts_return_type = "Promise<BoxOpenResponse>")]
+pub fn box_open(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::box_open)
+}
+
+#[napi(ts_return_type = "Promise<HpkeSealResponse>")]
+pub fn hpke_seal(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::hpke_seal)
+}
+
+#[napi(ts_return_type = "Promise<HpkeOpenResponse>")]
+pub fn hpke_open(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::hpke_open)
+}
+
+#[napi(ts_return_type = "Promise<MultiRecipientEnvelope>")]
+pub fn envelope_seal(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::envelope_seal)
+}
+
+#[napi(ts_return_type = "Promise<EnvelopeOpenResponse>")]
+pub fn envelope_open(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::envelope_open)
+}
+
+#[napi(ts_return_type = "Promise<MultiRecipientEnvelope>")]
+pub fn envelope_add_recipients(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::envelope_add_recipients)
+}
+
+#[napi(ts_return_type = "Promise<MultiRecipientEnvelope>")]
+pub fn envelope_remove_recipients(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::envelope_remove_recipients)
+}
+
+#[napi(ts_return_type = "Promise<AgeKeygenResponse>")]
+pub fn age_keygen() -> AsyncTask<ServiceTask> {
+    task(Value::Null, |service, ()| service.age_keygen())
+}
+
+#[napi(ts_return_type = "Promise<AgeEncryptResponse>")]
+pub fn age_encrypt(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::age_encrypt)
+}
+
+#[napi(ts_return_type = "Promise<AgeDecryptResponse>")]
+pub fn age_decrypt(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::age_decrypt)
+}
+
+#[napi(ts_return_type = "Promise<VerifyResponse>")]
+pub fn verify(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::verify)
+}
+
+#[napi(ts_return_type = "Promise<VerifyReleaseCertificateResponse>")]
+pub fn verify_release_certificate(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::verify_release_certificate)
+}
+
+#[napi(ts_return_type = "Promise<AuditVerifyResponse>")]
+pub fn verify_audit_chain(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::verify_audit_chain)
+}
+
+#[napi(ts_return_type = "Promise<VerifyAuditInclusionResponse>")]
+pub fn verify_audit_inclusion(req: Value) -> AsyncTask<ServiceTask> {
+    task(req, CryptoBoundaryService::verify_audit_inclusion)
+}
\ No newline at end of file
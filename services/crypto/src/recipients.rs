@@ -0,0 +1,120 @@
+//! Content-key wrapping for multi-recipient envelopes.
+//!
+//! A [`MultiRecipientEnvelope`](crate::MultiRecipientEnvelope) encrypts its
+//! payload once under a random content key and carries that key wrapped once
+//! per recipient: XChaCha20-Poly1305 for symmetric keys, single-shot HPKE base
+//! mode for X25519 public keys. Every wrap binds the recipient id, so stanzas
+//! cannot be relabelled. Adding a recipient needs the content key (any
+//! existing identity recovers it); removing one only drops its stanza, and
+//! does not revoke a content key the recipient has already seen.
+
+use base64::{Engine as _, engine::general_purpose};
+use secrecy::ExposeSecret;
+use zeroize::Zeroizing;
+
+use crate::aead::xchacha20poly1305_ietf;
+use crate::hpke;
+use crate::{CryptoError, Recipient, RecipientIdentity, RecipientStanza};
+
+pub const CONTENT_KEY_BYTES: usize = xchacha20poly1305_ietf::KEYBYTES;
+
+const LABEL: &[u8] = b"last-words envelope v1";
+
+fn aad(id: &str) -> Vec<u8> {
+    [LABEL, b"\0", id.as_bytes()].concat()
+}
+
+fn symmetric_key(key: &str) -> Result<xchacha20poly1305_ietf::Key, CryptoError> {
+    let bytes = Zeroizing::new(general_purpose::STANDARD.decode(key)?);
+    xchacha20poly1305_ietf::Key::from_slice(&bytes).ok_or_else(|| {
+        CryptoError::InvalidInput(format!("Key must be {} bytes", xchacha20poly1305_ietf::KEYBYTES))
+    })
+}
+
+impl Recipient {
+    pub fn id(&self) -> &str {
+        match self {
+            Recipient::Symmetric { id, .. } | Recipient::X25519 { id, .. } => id,
+        }
+    }
+}
+
+impl RecipientIdentity {
+    pub fn id(&self) -> &str {
+        match self {
+            RecipientIdentity::Symmetric { id, .. } | RecipientIdentity::X25519 { id, .. } => id,
+        }
+    }
+}
+
+impl RecipientStanza {
+    pub fn id(&self) -> &str {
+        match self {
+            RecipientStanza::Symmetric { id, .. } | RecipientStanza::X25519 { id, .. } => id,
+        }
+    }
+}
+
+/// Wraps `content_key` for `recipient`.
+pub fn wrap(recipient: &Recipient, content_key: &[u8; CONTENT_KEY_BYTES]) -> Result<RecipientStanza, CryptoError> {
+    match recipient {
+        Recipient::Symmetric { id, key } => {
+            let key = symmetric_key(key.expose_secret())?;
+            let nonce = xchacha20poly1305_ietf::gen_nonce();
+            let wrapped = xchacha20poly1305_ietf::seal(content_key, Some(&aad(id)), &nonce, &key);
+            Ok(RecipientStanza::Symmetric {
+                id: id.clone(),
+                nonce: general_purpose::STANDARD.encode(nonce.0),
+                wrapped_key: general_purpose::STANDARD.encode(wrapped),
+            })
+        }
+        Recipient::X25519 { id, public_key } => {
+            let public_key = hpke::decode_public_key(public_key)?;
+            let (enc, mut context) = hpke::setup_sender(&public_key, LABEL, None)?;
+            let wrapped = context.seal(&aad(id), content_key)?;
+            Ok(RecipientStanza::X25519 {
+                id: id.clone(),
+                enc: general_purpose::STANDARD.encode(enc),
+                wrapped_key: general_purpose::STANDARD.encode(wrapped),
+            })
+        }
+    }
+}
+
+/// Recovers the content key from the stanza matching `identity`.
+pub fn unwrap(
+    stanzas: &[RecipientStanza],
+    identity: &RecipientIdentity,
+) -> Result<Zeroizing<[u8; CONTENT_KEY_BYTES]>, CryptoError> {
+    let failed = || CryptoError::DecryptionFailed("Failed to unwrap content key".to_string());
+    let stanza = stanzas
+        .iter()
+        .find(|stanza| stanza.id() == identity.id())
+        .ok_or_else(|| CryptoError::InvalidInput(format!("No recipient with id {:?}", identity.id())))?;
+
+    let content_key = match (stanza, identity) {
+        (RecipientStanza::Symmetric { id, nonce, wrapped_key }, RecipientIdentity::Symmetric { key, .. }) => {
+            let key = symmetric_key(key.expose_secret())?;
+            let nonce = xchacha20poly1305_ietf::Nonce::from_slice(&general_purpose::STANDARD.decode(nonce)?)
+                .ok_or_else(|| CryptoError::InvalidInput("Invalid nonce".to_string()))?;
+            let wrapped = general_purpose::STANDARD.decode(wrapped_key)?;
+            xchacha20poly1305_ietf::open(&wrapped, Some(&aad(id)), &nonce, &key).map_err(|_| failed())?
+        }
+        (RecipientStanza::X25519 { id, enc, wrapped_key }, RecipientIdentity::X25519 { secret_key, .. }) => {
+            let enc = hpke::decode_enc(enc)?;
+            let secret_key = hpke::decode_secret_key(secret_key.expose_secret())?;
+            let wrapped = general_purpose::STANDARD.decode(wrapped_key)?;
+            let mut context = hpke::setup_receiver(&enc, &secret_key, LABEL, None)?;
+            context.open(&aad(id), &wrapped).map_err(|_| failed())?
+        }
+        _ => return Err(CryptoError::InvalidInput(format!("Recipient {:?} uses another key type", identity.id()))),
+    };
+
+    let content_key = Zeroizing::new(content_key);
+    let mut out = Zeroizing::new([0u8; CONTENT_KEY_BYTES]);
+    if content_key.len() != CONTENT_KEY_BYTES {
+        return Err(failed());
+    }
+    out.copy_from_slice(&content_key);
+    Ok(out)
+}
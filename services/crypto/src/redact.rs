@@ -0,0 +1,209 @@
+//! Secret values that never leak through formatting or logs.
+//!
+//! [`Secret`] wraps a `SecretString`: it is wiped on drop, and both `Debug` and
+//! `Display` print `[REDACTED]`. `Serialize` writes the real value, because the
+//! wire format needs it, except inside [`for_log`], which serialises the same
+//! value with every `Secret` replaced by `[REDACTED]`. Log request and response
+//! bodies with `for_log`, never with `serde_json::to_string` or `{:?}` on raw
+//! strings.
+
+use std::cell::Cell;
+use std::fmt;
+
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize, Serializer};
+
+pub const REDACTED: &str = "[REDACTED]";
+
+thread_local! {
+    static LOGGING: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(SecretString);
+
+impl ExposeSecret<str> for Secret {
+    fn expose_secret(&self) -> &str {
+        self.0.expose_secret()
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<SecretString> for Secret {
+    fn from(value: SecretString) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if LOGGING.with(Cell::get) {
+            serializer.serialize_str(REDACTED)
+        } else {
+            serializer.serialize_str(self.0.expose_secret())
+        }
+    }
+}
+
+/// Restores the previous mode even if serialisation panics.
+struct LoggingGuard(bool);
+
+impl Drop for LoggingGuard {
+    fn drop(&mut self) {
+        LOGGING.with(|logging| logging.set(self.0));
+    }
+}
+
+/// Serialises `value` as JSON for logs, with every [`Secret`] redacted.
+pub fn for_log<T: Serialize + ?Sized>(value: &T) -> String {
+    let _guard = LoggingGuard(LOGGING.with(|logging| logging.replace(true)));
+    serde_json::to_string(value).unwrap_or_else(|e| format!("<unserializable: {}>", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+
+    const SENTINEL: &str = "c2VudGluZWwtc2VjcmV0LXZhbHVl";
+
+    fn secret() -> Secret {
+        SENTINEL.into()
+    }
+
+    #[test]
+    fn test_secret_is_redacted_except_on_the_wire() {
+        let secret = secret();
+        assert_eq!(format!("{:?}", secret), REDACTED);
+        assert_eq!(format!("{}", secret), REDACTED);
+        assert_eq!(for_log(&secret), format!("\"{}\"", REDACTED));
+        assert_eq!(serde_json::to_string(&secret).unwrap(), format!("\"{}\"", SENTINEL));
+    }
+
+    /// Field names that hold key material or plaintext.
+    const SECRET_NAMES: &[&str] = &[
+        "password", "passphrase", "key", "secret", "plaintext", "share", "hash", "seed", "token", "identity",
+    ];
+
+    /// Public fields whose names look secret but whose values are not.
+    const ALLOWED: &[(&str, &str)] = &[
+        ("ArchiveProtection", "shares"),
+        ("AuditEntry", "hash"),
+        ("AuditEntry", "previous_hash"),
+        ("AuditInclusionProofResponse", "leaf_hash"),
+        ("AuditRecord", "hash"),
+        ("AuditRecord", "previous_hash"),
+        ("AuditTreeHead", "root_hash"),
+        ("BoxKeypairResponse", "public_key"),
+        ("BoxSealRequest", "public_key"),
+        ("HpkeOpenRequest", "sender_public_key"),
+        ("HpkeSealRequest", "public_key"),
+        ("KeyWrapResponse", "wrapped_key"),
+        ("KeyUnwrapRequest", "wrapped_key"),
+        ("OperationEvent", "key_id"),
+        ("Recipient", "public_key"),
+        ("RecipientStanza", "wrapped_key"),
+        ("ReleaseArchive", "wrapped_key"),
+        ("ReleaseAttestation", "content_hash"),
+        ("ReleaseCertificateRequest", "content_hash"),
+        ("ShamirSplitRequest", "shares"),
+        ("SigningKeysResponse", "keys"),
+        ("VerifyAuditInclusionRequest", "public_key"),
+        ("VerifyReleaseCertificateRequest", "public_key"),
+        ("VerifyRequest", "public_key"),
+    ];
+
+    /// Redacting types, and enums whose variants the lint has already held to
+    /// them; any other type on a secret-looking field fails the lint.
+    const REDACTING_TYPES: &[&str] = &["Secret", "SecretBuffer", "Share", "AgeIdentity", "RecipientIdentity"];
+
+    /// Lint: every public field of a public struct or enum variant whose name
+    /// suggests a secret must be held in a redacting type, so a new type cannot
+    /// reintroduce a raw `String` key that `#[derive(Debug)]` would print.
+    #[test]
+    fn test_secret_fields_use_redacting_types() {
+        let mut violations = Vec::new();
+        scan(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src"), &mut violations);
+        assert!(violations.is_empty(), "secret fields without a redacting type:\n{}", violations.join("\n"));
+    }
+
+    fn scan(dir: &Path, violations: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                scan(&path, violations);
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                lint_file(&path, &fs::read_to_string(&path).unwrap(), violations);
+            }
+        }
+    }
+
+    fn lint_file(path: &Path, source: &str, violations: &mut Vec<String>) {
+        // The public type being read, and whether it is a struct (fields are
+        // `pub`) or an enum (fields sit in variants, one line or several).
+        let mut current: Option<(&str, bool)> = None;
+        for line in source.lines().map(str::trim) {
+            let header = line
+                .strip_prefix("pub struct ")
+                .map(|rest| (rest, true))
+                .or_else(|| line.strip_prefix("pub enum ").map(|rest| (rest, false)));
+            if let Some((rest, is_struct)) = header {
+                let name = rest.split(|c: char| !c.is_alphanumeric() && c != '_').next().unwrap();
+                current = rest.ends_with('{').then_some((name, is_struct));
+                continue;
+            }
+            if line == "}" {
+                current = None;
+                continue;
+            }
+            let Some((owner, is_struct)) = current else { continue };
+            if line.starts_with("//") || line.starts_with("#[") {
+                continue;
+            }
+            let fields: Vec<&str> = match (is_struct, line.split_once('{')) {
+                (true, _) => line.strip_prefix("pub ").into_iter().collect(),
+                (false, Some((_, inline))) => inline.split(',').collect(),
+                (false, None) => vec![line],
+            };
+
+            for (field, ty) in fields.iter().filter_map(|f| f.split_once(':')) {
+                let field = field.trim();
+                let looks_secret = SECRET_NAMES.iter().any(|name| field.contains(name));
+                let allowed = ALLOWED.contains(&(owner, field));
+                let redacting = REDACTING_TYPES.iter().any(|t| {
+                    ty.split(|c: char| !c.is_alphanumeric() && c != '_').any(|word| word == *t)
+                });
+                if looks_secret && !allowed && !redacting {
+                    let ty = ty.trim().trim_end_matches([',', '}', ' ']);
+                    violations.push(format!("{}: {}.{} has type {}", path.display(), owner, field, ty));
+                }
+            }
+        }
+    }
+}
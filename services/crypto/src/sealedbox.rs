@@ -0,0 +1,100 @@
+//! Anonymous public-key encryption: libsodium's `crypto_box_seal`.
+//!
+//! A sealed box is an ephemeral X25519 public key followed by an
+//! XSalsa20-Poly1305 box of the message, so only the holder of the recipient's
+//! secret key can open it and the sender stays anonymous. As with [`crate::aead`],
+//! the `sodium` feature uses libsodium and the pure-Rust backend (`crypto_box`)
+//! produces and opens exactly the same bytes.
+
+#[cfg(feature = "sodium")]
+pub use self::sodium as curve25519xsalsa20poly1305;
+
+#[cfg(not(feature = "sodium"))]
+pub use self::pure as curve25519xsalsa20poly1305;
+
+/// libsodium's `box_` keys with the `sealedbox` functions alongside.
+#[cfg(feature = "sodium")]
+pub mod sodium {
+    pub use sodiumoxide::crypto::box_::{PUBLICKEYBYTES, PublicKey, SECRETKEYBYTES, SecretKey, gen_keypair};
+    pub use sodiumoxide::crypto::sealedbox::{SEALBYTES, open, seal};
+}
+
+/// The pure-Rust backend, always built so it can be checked against libsodium.
+pub mod pure {
+    use rand::rngs::OsRng;
+    use zeroize::Zeroize;
+
+    pub const PUBLICKEYBYTES: usize = crypto_box::KEY_SIZE;
+    pub const SECRETKEYBYTES: usize = crypto_box::KEY_SIZE;
+    pub const SEALBYTES: usize = crypto_box::SEALBYTES;
+
+    pub struct PublicKey(pub [u8; PUBLICKEYBYTES]);
+
+    pub struct SecretKey(pub [u8; SECRETKEYBYTES]);
+
+    impl PublicKey {
+        pub fn from_slice(bs: &[u8]) -> Option<PublicKey> {
+            bs.try_into().ok().map(PublicKey)
+        }
+    }
+
+    impl SecretKey {
+        pub fn from_slice(bs: &[u8]) -> Option<SecretKey> {
+            bs.try_into().ok().map(SecretKey)
+        }
+
+        pub fn public_key(&self) -> PublicKey {
+            PublicKey(crypto_box::SecretKey::from_bytes(self.0).public_key().to_bytes())
+        }
+    }
+
+    impl Drop for SecretKey {
+        fn drop(&mut self) {
+            self.0.zeroize();
+        }
+    }
+
+    pub fn gen_keypair() -> (PublicKey, SecretKey) {
+        let sk = crypto_box::SecretKey::generate(&mut OsRng);
+        (PublicKey(sk.public_key().to_bytes()), SecretKey(sk.to_bytes()))
+    }
+
+    pub fn seal(m: &[u8], pk: &PublicKey) -> Vec<u8> {
+        crypto_box::PublicKey::from_bytes(pk.0)
+            .seal(&mut OsRng, m)
+            .expect("sealed box message too long")
+    }
+
+    #[allow(clippy::result_unit_err)]
+    pub fn open(c: &[u8], pk: &PublicKey, sk: &SecretKey) -> Result<Vec<u8>, ()> {
+        let sk = crypto_box::SecretKey::from_bytes(sk.0);
+        if sk.public_key().to_bytes() != pk.0 {
+            return Err(());
+        }
+        sk.unseal(c).map_err(|_| ())
+    }
+}
+
+#[cfg(all(test, feature = "sodium"))]
+mod tests {
+    use super::{pure, sodium};
+
+    #[test]
+    fn test_pure_backend_matches_libsodium() {
+        crate::aead::init().unwrap();
+        let (pk, sk) = sodium::gen_keypair();
+        let pure_sk = pure::SecretKey::from_slice(&sk.0).unwrap();
+        let pure_pk = pure_sk.public_key();
+        assert_eq!(pure_pk.0, pk.0);
+
+        let sealed = sodium::seal(b"last words", &pk);
+        assert_eq!(sealed.len(), b"last words".len() + pure::SEALBYTES);
+        assert_eq!(pure::open(&sealed, &pure_pk, &pure_sk).unwrap(), b"last words");
+
+        let sealed = pure::seal(b"", &pure_pk);
+        assert_eq!(sodium::open(&sealed, &pk, &sk).unwrap(), b"");
+
+        let (other_pk, other_sk) = pure::gen_keypair();
+        assert!(pure::open(&sealed, &other_pk, &other_sk).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+//! Locked, guard-paged memory for long-lived keys.
+//!
+//! With the `sodium` feature, [`SecretBuffer`] is allocated by `sodium_malloc`:
+//! the pages are `mlock`ed (never swapped), excluded from core dumps, bracketed
+//! by guard pages and a canary, and made read-only once filled. `sodium_free`
+//! wipes them on drop. Without libsodium (wasm32) the buffer falls back to a
+//! zeroize-on-drop heap allocation.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::RwLock;
+
+use crate::CryptoError;
+
+pub struct SecretBuffer {
+    inner: imp::Buffer,
+}
+
+impl SecretBuffer {
+    /// Copies `bytes` into a new locked buffer. The caller remains responsible
+    /// for wiping its own copy.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, CryptoError> {
+        Ok(Self { inner: imp::Buffer::from_slice(bytes)? })
+    }
+
+    pub fn expose(&self) -> &[u8] {
+        self.inner.as_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.expose().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Debug for SecretBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBuffer([REDACTED; {}])", self.len())
+    }
+}
+
+#[cfg(feature = "sodium")]
+mod imp {
+    use std::ffi::c_void;
+    use std::ptr::NonNull;
+
+    use crate::CryptoError;
+
+    pub struct Buffer {
+        ptr: NonNull<u8>,
+        len: usize,
+    }
+
+    // The buffer is read-only after construction and owned exclusively.
+    unsafe impl Send for Buffer {}
+    unsafe impl Sync for Buffer {}
+
+    impl Buffer {
+        pub fn from_slice(bytes: &[u8]) -> Result<Self, CryptoError> {
+            // sodium_malloc aborts if libsodium was never initialised; init is idempotent.
+            crate::aead::init()
+                .map_err(|_| CryptoError::SecureMemory("Failed to initialize libsodium".to_string()))?;
+            // sodium_malloc(0) is legal but returns a pointer we must not write.
+            let size = bytes.len().max(1);
+            let ptr = unsafe { libsodium_sys::sodium_malloc(size) } as *mut u8;
+            let ptr = NonNull::new(ptr)
+                .ok_or_else(|| CryptoError::SecureMemory("sodium_malloc failed".to_string()))?;
+
+            unsafe {
+                std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr.as_ptr(), bytes.len());
+                if libsodium_sys::sodium_mprotect_readonly(ptr.as_ptr() as *mut c_void) != 0 {
+                    libsodium_sys::sodium_free(ptr.as_ptr() as *mut c_void);
+                    return Err(CryptoError::SecureMemory("sodium_mprotect_readonly failed".to_string()));
+                }
+            }
+
+            Ok(Self { ptr, len: bytes.len() })
+        }
+
+        pub fn as_slice(&self) -> &[u8] {
+            unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+        }
+    }
+
+    impl Drop for Buffer {
+        fn drop(&mut self) {
+            // sodium_free makes the region writable, wipes it and unlocks it.
+            unsafe { libsodium_sys::sodium_free(self.ptr.as_ptr() as *mut c_void) }
+        }
+    }
+}
+
+#[cfg(not(feature = "sodium"))]
+mod imp {
+    use zeroize::Zeroizing;
+
+    use crate::CryptoError;
+
+    pub struct Buffer(Zeroizing<Vec<u8>>);
+
+    impl Buffer {
+        pub fn from_slice(bytes: &[u8]) -> Result<Self, CryptoError> {
+            Ok(Self(Zeroizing::new(bytes.to_vec())))
+        }
+
+        pub fn as_slice(&self) -> &[u8] {
+            &self.0
+        }
+    }
+}
+
+/// Named long-lived keys held by the service, each in its own [`SecretBuffer`].
+#[derive(Debug, Default)]
+pub struct KeyStore {
+    keys: RwLock<HashMap<String, SecretBuffer>>,
+}
+
+impl KeyStore {
+    /// Stores a copy of `key` under `id`, replacing (and wiping) any previous key.
+    pub fn insert(&self, id: &str, key: &[u8]) -> Result<(), CryptoError> {
+        let buffer = SecretBuffer::from_slice(key)?;
+        self.keys
+            .write()
+            .map_err(|_| CryptoError::SecureMemory("Key store lock poisoned".to_string()))?
+            .insert(id.to_string(), buffer);
+        Ok(())
+    }
+
+    /// Runs `f` with the key bytes, without copying them out of locked memory.
+    pub fn with_key<T>(&self, id: &str, f: impl FnOnce(&[u8]) -> T) -> Result<Option<T>, CryptoError> {
+        let keys = self
+            .keys
+            .read()
+            .map_err(|_| CryptoError::SecureMemory("Key store lock poisoned".to_string()))?;
+        Ok(keys.get(id).map(|key| f(key.expose())))
+    }
+
+    pub fn remove(&self, id: &str) -> Result<bool, CryptoError> {
+        Ok(self
+            .keys
+            .write()
+            .map_err(|_| CryptoError::SecureMemory("Key store lock poisoned".to_string()))?
+            .remove(id)
+            .is_some())
+    }
+
+    pub fn ids(&self) -> Result<Vec<String>, CryptoError> {
+        let keys = self
+            .keys
+            .read()
+            .map_err(|_| CryptoError::SecureMemory("Key store lock poisoned".to_string()))?;
+        let mut ids: Vec<String> = keys.keys().cloned().collect();
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CryptoBoundaryService;
+
+    #[test]
+    fn test_secret_buffer_round_trip() {
+        // No service first: the buffer must initialise libsodium itself.
+        let buffer = SecretBuffer::from_slice(&[42u8; 32]).unwrap();
+        assert_eq!(buffer.expose(), &[42u8; 32]);
+        assert_eq!(format!("{:?}", buffer), "SecretBuffer([REDACTED; 32])");
+
+        let empty = SecretBuffer::from_slice(&[]).unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_key_store() {
+        let service = CryptoBoundaryService::new();
+        let store = service.key_store();
+        store.insert("master", &[1u8; 32]).unwrap();
+        store.insert("master", &[2u8; 32]).unwrap();
+
+        assert_eq!(store.with_key("master", |k| k[0]).unwrap(), Some(2));
+        assert_eq!(store.with_key("missing", |k| k[0]).unwrap(), None);
+        assert_eq!(store.ids().unwrap(), vec!["master".to_string()]);
+        assert!(store.remove("master").unwrap());
+        assert!(!store.remove("master").unwrap());
+    }
+}
@@ -0,0 +1,435 @@
+//! Known-answer self-tests of everything the service exposes.
+//!
+//! The primitive tests run published vectors through the same backends the
+//! service uses (libsodium or the pure-Rust AEAD, `sha2`, `hkdf`, `argon2`,
+//! and the service's own RFC 6962 and RFC 8785 code) and compare
+//! the output byte for byte. The operation tests then feed frozen v1 outputs
+//! back through the service's own methods, so a change in encoding or
+//! parameter handling is caught as well. A mismatch means the build or the
+//! platform is broken and nothing it produces can be trusted.
+//!
+//! [`CryptoBoundaryService::try_new`] runs all of them at power-on and refuses
+//! to construct a service if any fails; readiness re-runs them periodically.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chrono::{TimeZone, Utc};
+use base64::{Engine as _, engine::general_purpose};
+use hkdf::Hkdf;
+use secrecy::ExposeSecret;
+use serde::Serialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::aead::xchacha20poly1305_ietf;
+use crate::age;
+use crate::auditlog;
+use crate::canonical;
+use crate::events;
+use crate::merkle;
+use crate::{
+    AeadDecryptRequest, AgeDecryptRequest, AgeIdentity, AuditEvent, AuditRecord, BoxOpenRequest, CryptoBoundaryService,
+    CryptoError, HpkeOpenRequest, KdfRequest, KeyUnwrapRequest, ShamirCombineRequest, VerifyRequest,
+};
+
+/// The outcome of one known-answer test.
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestResult {
+    pub algorithm: &'static str,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+type KnownAnswerTest = fn(&CryptoBoundaryService) -> Result<(), CryptoError>;
+
+const TESTS: &[(&str, KnownAnswerTest)] = &[
+    ("XChaCha20-Poly1305", xchacha20poly1305),
+    ("SHA-256", sha256),
+    ("HKDF-SHA256", hkdf_sha256),
+    ("Argon2id", argon2id),
+    ("HPKE", hpke_open),
+    ("Ed25519", ed25519),
+    ("RFC 6962", merkle_root),
+    ("RFC 8785", canonical_json),
+    ("audit/record-hash", audit_record_hash),
+    ("kdf/argon2id", kdf_phc),
+    ("aead/decrypt", aead_decrypt),
+    ("key/unwrap", key_unwrap),
+    ("shamir/combine", shamir_combine),
+    ("box/open", box_open),
+    ("hpke/open (auth)", hpke_open_auth),
+    ("age/decrypt", age_decrypt),
+    ("age/decrypt (scrypt)", age_decrypt_scrypt),
+    ("verify", verify),
+];
+
+/// Runs every known-answer test and reports each result. The operations
+/// they run through the service are synthetic and emit no events.
+pub fn run(service: &CryptoBoundaryService) -> Vec<SelfTestResult> {
+    events::unobserved(|| {
+        TESTS
+            .iter()
+            .map(|(algorithm, test)| {
+                let result = test(service);
+                SelfTestResult { algorithm, passed: result.is_ok(), error: result.err().map(|e| e.to_string()) }
+            })
+            .collect()
+    })
+}
+
+/// Runs every known-answer test, failing with the names of those that did not pass.
+pub fn power_on(service: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let failed: Vec<String> = run(service)
+        .into_iter()
+        .filter(|result| !result.passed)
+        .map(|result| format!("{} ({})", result.algorithm, result.error.unwrap_or_default()))
+        .collect();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(CryptoError::SelfTestFailed(failed.join(", ")))
+    }
+}
+
+fn expect(algorithm: &str, actual: &[u8], expected_hex: &str) -> Result<(), CryptoError> {
+    if hex::encode(actual) == expected_hex {
+        Ok(())
+    } else {
+        Err(CryptoError::SelfTestFailed(format!("{} known-answer mismatch", algorithm)))
+    }
+}
+
+fn unhex(s: &str) -> Vec<u8> {
+    hex::decode(s).expect("valid test vector")
+}
+
+fn b64(bytes: &[u8]) -> String {
+    general_purpose::STANDARD.encode(bytes)
+}
+
+/// draft-irtf-cfrg-xchacha-03, Appendix A.3.1.
+fn xchacha20poly1305(_: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+    let aad = unhex("50515253c0c1c2c3c4c5c6c7");
+    let key = xchacha20poly1305_ietf::Key::from_slice(&unhex(
+        "808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f",
+    )).expect("32-byte key");
+    let nonce = xchacha20poly1305_ietf::Nonce::from_slice(&unhex(
+        "404142434445464748494a4b4c4d4e4f5051525354555657",
+    )).expect("24-byte nonce");
+
+    let sealed = xchacha20poly1305_ietf::seal(plaintext, Some(&aad), &nonce, &key);
+    expect(
+        "XChaCha20-Poly1305",
+        &sealed,
+        concat!(
+            "bd6d179d3e83d43b9576579493c0e939572a1700252bfaccbed2902c21396cbb",
+            "731c7f1b0b4aa6440bf3a82f4eda7e39ae64c6708c54c216cb96b72e1213b452",
+            "2f8c9ba40db5d945b11b69b982c1bb9e3f3fac2bc369488f76b2383565d3fff9",
+            "21f9664c97637da9768812f615c68b13b52e",
+            "c0875924c1c7987947deafd8780acf49",
+        ),
+    )?;
+
+    let opened = xchacha20poly1305_ietf::open(&sealed, Some(&aad), &nonce, &key)
+        .map_err(|_| CryptoError::SelfTestFailed("XChaCha20-Poly1305 failed to open its own vector".to_string()))?;
+    if opened != plaintext {
+        return Err(CryptoError::SelfTestFailed("XChaCha20-Poly1305 round trip mismatch".to_string()));
+    }
+
+    let mut tampered = sealed;
+    tampered[0] ^= 1;
+    if xchacha20poly1305_ietf::open(&tampered, Some(&aad), &nonce, &key).is_ok() {
+        return Err(CryptoError::SelfTestFailed("XChaCha20-Poly1305 accepted a forged ciphertext".to_string()));
+    }
+    Ok(())
+}
+
+/// FIPS 180-2, Appendix B.1.
+fn sha256(_: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    expect("SHA-256", &Sha256::digest(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+}
+
+/// RFC 5869, test case 1.
+fn hkdf_sha256(_: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let ikm = [0x0bu8; 22];
+    let salt = unhex("000102030405060708090a0b0c");
+    let info = unhex("f0f1f2f3f4f5f6f7f8f9");
+
+    let mut okm = [0u8; 42];
+    Hkdf::<Sha256>::new(Some(&salt), &ikm)
+        .expand(&info, &mut okm)
+        .map_err(|e| CryptoError::SelfTestFailed(format!("HKDF-SHA256 expand failed: {}", e)))?;
+    expect(
+        "HKDF-SHA256",
+        &okm,
+        "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865",
+    )
+}
+
+/// RFC 9106, section 5.3.
+fn argon2id(_: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let secret = [0x03u8; 8];
+    let params = argon2::ParamsBuilder::new()
+        .m_cost(32)
+        .t_cost(3)
+        .p_cost(4)
+        .data(argon2::AssociatedData::new(&[0x04u8; 12]).expect("short associated data"))
+        .output_len(32)
+        .build()
+        .map_err(|e| CryptoError::SelfTestFailed(format!("Argon2id parameters rejected: {}", e)))?;
+    let argon2 = Argon2::new_with_secret(&secret, Algorithm::Argon2id, Version::V0x13, params)
+        .map_err(|e| CryptoError::SelfTestFailed(format!("Argon2id setup failed: {}", e)))?;
+
+    let mut tag = [0u8; Params::DEFAULT_OUTPUT_LEN];
+    argon2
+        .hash_password_into(&[0x01u8; 32], &[0x02u8; 16], &mut tag)
+        .map_err(|e| CryptoError::SelfTestFailed(format!("Argon2id failed: {}", e)))?;
+    expect("Argon2id", &tag, "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659")
+}
+
+/// RFC 9180, Appendix A.2.1 (base mode, first message), through the service.
+fn hpke_open(service: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let response = service.hpke_open(HpkeOpenRequest {
+        enc: b64(&unhex("1afa08d3dec047a643885163f1180476fa7ddb54c6a8029ea33f95796bf2ac4a")),
+        ciphertext: b64(&unhex(
+            "1c5250d8034ec2b784ba2cfd69dbdb8af406cfe3ff938e131f0def8c8b60b4db21993c62ce81883d2dd1b51a28",
+        )),
+        secret_key: b64(&unhex("8057991eef8f1f1af18f4a9491d16a1ce333f695d4db8e38da75975c4478e0fb")).into(),
+        info: Some("Ode on a Grecian Urn".to_string()),
+        additional_data: Some("Count-0".to_string()),
+        sender_public_key: None,
+    })?;
+    expect("HPKE", response.plaintext.expose_secret().as_bytes(), &hex::encode("Beauty is truth, truth beauty"))
+}
+
+/// The eight-leaf tree from the certificate-transparency reference tests
+/// (tests/vectors/rfc6962.json), with the audit path of leaf 0.
+fn merkle_root(_: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let data = ["", "00", "10", "2021", "3031", "40414243", "5051525354555657", "606162636465666768696a6b6c6d6e6f"];
+    let leaves: Vec<merkle::Hash> = data.iter().map(|leaf| merkle::leaf_hash(&unhex(leaf))).collect();
+    let root = merkle::root(&leaves);
+    expect("RFC 6962", &root, "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328")?;
+
+    let path = merkle::inclusion_proof(&leaves, 0);
+    expect(
+        "RFC 6962",
+        &path.concat(),
+        concat!(
+            "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+            "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+            "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+        ),
+    )?;
+    if !merkle::verify_inclusion(&leaves[0], 0, 8, &path, &root)
+        || merkle::verify_inclusion(&leaves[1], 0, 8, &path, &root)
+    {
+        return Err(CryptoError::SelfTestFailed("RFC 6962 inclusion proof check is broken".to_string()));
+    }
+    Ok(())
+}
+
+/// RFC 8785, section 3.2.2.
+fn canonical_json(_: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let input: Value = serde_json::from_str(r#"{
+        "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+        "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+        "literals": [null, true, false]
+    }"#).map_err(|e| CryptoError::SelfTestFailed(format!("RFC 8785 vector did not parse: {}", e)))?;
+    expect(
+        "RFC 8785",
+        &canonical::to_vec(&input)?,
+        &hex::encode(concat!(
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"#,
+            r#""string":"€$\u000f\nA'B\"\\\\\"/"}"#,
+        )),
+    )
+}
+
+// The operation vectors below are frozen outputs of the v1 service; they
+// must keep decoding to the same values for stored data to stay readable.
+
+fn kdf_phc(service: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let response = service.kdf_argon2id(KdfRequest {
+        password: "last-words-kat".into(),
+        salt: Some(b64(&[0x5a; 16])),
+        memory: Some(64),
+        iterations: Some(1),
+        parallelism: Some(1),
+    })?;
+    expect(
+        "kdf/argon2id",
+        response.hash.expose_secret().as_bytes(),
+        &hex::encode("$argon2id$v=19$m=64,t=1,p=1$WlpaWlpaWlpaWlpaWlpaWg$3Q9vUgPyCzJIak+bg9BgHRzmuqp6j+9Sjw2s+w7MHa8"),
+    )
+}
+
+fn aead_decrypt(service: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let response = service.aead_decrypt(AeadDecryptRequest {
+        ciphertext: "5AQSNeVAIHlTPN+BAoKYKUJAeOqbSszb3+sGMCa3".to_string(),
+        key: b64(&[0x42; 32]).into(),
+        nonce: "CpzDvCT8rA+4GsEn/XUNUa5MO8D5Xmim".to_string(),
+        additional_data: Some("kat".to_string()),
+    })?;
+    expect("aead/decrypt", response.plaintext.expose_secret().as_bytes(), &hex::encode("last-words-kat"))
+}
+
+/// The SHA-256 of a v1 audit record's canonical JSON, which chains the log.
+fn audit_record_hash(_: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let record = AuditRecord {
+        sequence: 0,
+        timestamp: Utc.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).single().expect("valid timestamp"),
+        event: AuditEvent {
+            operation: "release".to_string(),
+            resource: "will".to_string(),
+            action: "approve".to_string(),
+            result: "success".to_string(),
+            user_id: Some("user-1".to_string()),
+            session_id: None,
+            details: Some(json!({ "approver": { "id": "exec-2", "role": "executor" } })),
+        },
+        previous_hash: None,
+        hash: String::new(),
+    };
+    expect(
+        "audit/record-hash",
+        auditlog::record_hash(&record)?.as_bytes(),
+        &hex::encode("837bca9478e6e839dc7842a138ec151def8622e51af4599cf9004c1b56679958"),
+    )
+}
+
+fn key_unwrap(service: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let response = service.key_unwrap(KeyUnwrapRequest {
+        master_key: b64(&[0x11; 32]).into(),
+        wrapped_key: concat!(
+            "NKZL1IXTervtXC1KDiMm/lhOeI4ZGksk2l9bSItHuHxWUQEYzxqAKQKMPYHW62U1",
+            "Wp4A7MLe1iEVC0lWL/Q7s3lvDsP5QQP+",
+        ).to_string(),
+        salt: "6AeQ9zXMTC2jWJ3Hicnjj6d9MQ+9uUhYyETV6qM6JFA=".to_string(),
+    })?;
+    expect("key/unwrap", response.unwrapped_key.expose_secret().as_bytes(), &hex::encode(b64(&[0x22; 32])))
+}
+
+fn shamir_combine(service: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    // Shares 1 and 3 of a 2-of-3 split.
+    let response = service.shamir_combine(ShamirCombineRequest {
+        shares: vec!["AVKUEKpOxunBTxeuAU4zPNA=".into(), "A5DHVoW0MUA4t1+JZbQzIgs=".into()],
+    })?;
+    expect("shamir/combine", response.secret.expose_secret().as_bytes(), &hex::encode(b64(&[0x33; 16])))
+}
+
+fn box_open(service: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let response = service.box_open(BoxOpenRequest {
+        ciphertext: concat!(
+            "ddeYdhzjEgRVHwNcvjCohdc440N5/GmAtF0LeN02zQAd2Ig3y835cbHa9QsSz34o",
+            "wKsJrgIRqZJ8hmdYHok=",
+        ).to_string(),
+        secret_key: b64(&[0x44; 32]).into(),
+    })?;
+    expect("box/open", response.plaintext.expose_secret().as_bytes(), &hex::encode("last-words-kat"))
+}
+
+/// RFC 8032, section 7.1, TEST 2.
+fn ed25519(_: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let seed: [u8; 32] = unhex("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb")
+        .try_into()
+        .expect("32-byte seed");
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(&seed);
+    expect(
+        "Ed25519",
+        signing_key.verifying_key().as_bytes(),
+        "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+    )?;
+
+    let signature = ed25519_dalek::Signer::sign(&signing_key, &[0x72]);
+    expect(
+        "Ed25519",
+        &signature.to_bytes(),
+        concat!(
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da",
+            "085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ),
+    )?;
+    if signing_key.verifying_key().verify_strict(&[0x73], &signature).is_ok() {
+        return Err(CryptoError::SelfTestFailed("Ed25519 accepted a forged signature".to_string()));
+    }
+    Ok(())
+}
+
+/// A file written by the reference `age` implementation, grease stanza included.
+fn age_decrypt(service: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let identity = age::encode_identity(&x25519_dalek::StaticSecret::from([0x45; 32]));
+    let response = service.age_decrypt(AgeDecryptRequest {
+        file: concat!(
+            "YWdlLWVuY3J5cHRpb24ub3JnL3YxCi0+IFgyNTUxOSBDUzBLYUtwMFBZc2NZOVlB",
+            "UVlnOHdxNFpIUmZEMXhtcTJSZHdOcnNoaHdNCjVsYUhTanhERzJST01iM0RDY0E5",
+            "VmwvaEt4VVlIUFdlMjRrV1B6NzQ3RzgKLT4gfkUoLWdyZWFzZSBGUjtgIGssVVYK",
+            "YTE3SmJ6T2U1R1FyOGcwRFI4ME10TC9xazJEM2craVJDdGh2MkpEdzRCTDhIQ0RO",
+            "M1JjanpxVVU3QWgyTUFqSwpZeWhBNDRRMVF4L0ZvSVgveFlXTE5jL29lWWN1dTBK",
+            "b29ESTI4d2dvMkx3eXVDQnFZNVNYCi0tLSA2a3lSTFVjenBkaE9kbFRiMzF0VS9B",
+            "dVp6YW85Y2NvelF0amgyZ094bGlnChIbsusHhlmNBYNYTLLOuI6e4ypOJFzU1ICM",
+            "GXO2DokxqDLU5K0DenyblOc4Kec=",
+        ).to_string(),
+        identity: AgeIdentity::X25519 { identity: identity.as_str().into() },
+    })?;
+    expect("age/decrypt", response.plaintext.expose_secret().as_bytes(), &hex::encode("last-words-kat"))
+}
+
+/// Auth mode, from the static sender key `[0x48; 32]` to `[0x47; 32]`.
+fn hpke_open_auth(service: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let response = service.hpke_open(HpkeOpenRequest {
+        enc: "NrvMfR38ViVv8rLjRkja/gqVBwUYAr4K9RAnSM9T5xw=".to_string(),
+        ciphertext: "nE1EQ6hn1HzSmaj5GHpeAKyLWvZd4jZJenaXTUdU".to_string(),
+        secret_key: b64(&[0x47; 32]).into(),
+        info: Some("kat".to_string()),
+        additional_data: Some("kat".to_string()),
+        sender_public_key: Some("kXeyMnjL8PPRfDbyrMm1XpyF+HsiClOG7DcNZj4g4zc=".to_string()),
+    })?;
+    expect("hpke/open (auth)", response.plaintext.expose_secret().as_bytes(), &hex::encode("last-words-kat"))
+}
+
+/// A passphrase file written by the reference `age` implementation at work factor 10.
+fn age_decrypt_scrypt(service: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let response = service.age_decrypt(AgeDecryptRequest {
+        file: concat!(
+            "YWdlLWVuY3J5cHRpb24ub3JnL3YxCi0+IHNjcnlwdCAxYm9pU0g3KzFxOTk5NmVs",
+            "MnpPYWtBIDEwCmxwUEZLVHdmQStxQjR5UEhMbFh0ekcwY1N3cWdNTDdMRkZCaUlv",
+            "NXJacUEKLS0tIEhlaUFEdFVrSjNyNU9aRHVoSDRBZ0Z1ZTlXT3VlUXFqMlpFUGhP",
+            "NzVxMXMKyFcuhIwuMEJml/+WN4mh7KAZg+yubtTkKn4Dw+Y25TC4QgbCT7GQNWFD",
+            "jYf0jg==",
+        ).to_string(),
+        identity: AgeIdentity::Scrypt { passphrase: "last-words-kat".into() },
+    })?;
+    expect("age/decrypt (scrypt)", response.plaintext.expose_secret().as_bytes(), &hex::encode("last-words-kat"))
+}
+
+/// A signature by the seed `[0x46; 32]` in the `self-test` context.
+fn verify(service: &CryptoBoundaryService) -> Result<(), CryptoError> {
+    let response = service.verify(VerifyRequest {
+        context: "self-test".to_string(),
+        message: "last-words-kat".to_string(),
+        signature: concat!(
+            "ueMz5dcnQpAmylX6uMMFdJBS8tKb2ZCTMOO7qyzsyIKMv1rjkC++7OwNAb2NVTZH",
+            "9K7nz7kursOI4zm7opq+Ag==",
+        ).to_string(),
+        kid: Some("GGeNrGXcKjX5-cjBURw66TEIvvBdpXGysNqkpZKFUew".to_string()),
+        public_key: Some("7pOk9m+NFrgZu5vrn/zN/NwUEuh/7moyTCqZoeDmcUg=".to_string()),
+    })?;
+    if !response.valid {
+        return Err(CryptoError::SelfTestFailed("verify known-answer mismatch".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_known_answers_pass() {
+        let service = CryptoBoundaryService::new();
+        for result in run(&service) {
+            assert!(result.passed, "{}: {:?}", result.algorithm, result.error);
+        }
+    }
+}
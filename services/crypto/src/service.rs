@@ -0,0 +1,1399 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use argon2::{Argon2, PasswordHasher};
+use chrono::{SubsecRound, Utc};
+use ed25519_dalek::VerifyingKey;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use base64::{Engine as _, engine::general_purpose};
+use rand::{RngCore, rngs::OsRng};
+use secrecy::ExposeSecret;
+use zeroize::Zeroizing;
+
+use crate::age;
+use crate::archive::{self, ReleaseArchive};
+use crate::auditlog::{self, AuditLog};
+use crate::canonical;
+use crate::certificate;
+use crate::deadman::{self, Clock, SystemClock};
+use crate::events;
+use crate::aead::{self, xchacha20poly1305_ietf};
+use crate::hpke;
+use crate::recipients;
+use crate::sealedbox::curve25519xsalsa20poly1305;
+use crate::secure::KeyStore;
+use crate::selftest;
+use crate::shamir;
+use crate::signing::{self, SigningKeys};
+use crate::error::CryptoError;
+use crate::redact::Secret;
+use crate::types::*;
+
+pub struct CryptoBoundaryService {
+    // Long-lived keys live only here, in locked memory
+    key_store: KeyStore,
+    signing_keys: SigningKeys,
+    audit_log: AuditLog,
+    /// The latest row of every alive check the API has sent, by id, for the
+    /// scheduler to keep evaluating between the API's calls.
+    alive_checks: RwLock<BTreeMap<String, AliveCheck>>,
+}
+
+impl Default for CryptoBoundaryService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CryptoBoundaryService {
+    /// Panics if the backend cannot be initialised or a power-on self-test
+    /// fails; use [`try_new`](Self::try_new) to handle that instead.
+    pub fn new() -> Self {
+        Self::try_new().expect("Crypto boundary failed to start")
+    }
+
+    /// Initialises the backend and runs the power-on known-answer tests for
+    /// every algorithm; no service is returned if any of them fails.
+    pub fn try_new() -> Result<Self, CryptoError> {
+        aead::init().map_err(|_| CryptoError::SelfTestFailed("Failed to initialize libsodium".to_string()))?;
+        
+        let service = Self {
+            key_store: KeyStore::default(),
+            signing_keys: SigningKeys::default(),
+            audit_log: AuditLog::default(),
+            alive_checks: RwLock::default(),
+        };
+        selftest::power_on(&service)?;
+        Ok(service)
+    }
+
+    pub fn key_store(&self) -> &KeyStore {
+        &self.key_store
+    }
+
+    pub fn kdf_argon2id(&self, req: KdfRequest) -> Result<KdfResponse, CryptoError> {
+        events::observe("kdf", None, || {
+            let salt_bytes = match &req.salt {
+                Some(s) => general_purpose::STANDARD.decode(s)?,
+                None => {
+                    let mut salt = [0u8; 32];
+                    OsRng.fill_bytes(&mut salt);
+                    salt.to_vec()
+                }
+            };
+            // Shorter salts make the PHC encoder panic rather than return an error.
+            if salt_bytes.len() < argon2::MIN_SALT_LEN {
+                return Err(CryptoError::InvalidInput(
+                    format!("Salt must be at least {} bytes", argon2::MIN_SALT_LEN)
+                ));
+            }
+
+            let memory = req.memory.unwrap_or(65536); // 64 MB
+            let iterations = req.iterations.unwrap_or(3);
+            let parallelism = req.parallelism.unwrap_or(1);
+
+            let params = argon2::Params::new(memory, iterations, parallelism, Some(32))
+                .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+        
+            let argon2 = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+        
+            use argon2::password_hash::SaltString;
+            let salt_string = SaltString::encode_b64(&salt_bytes)
+                .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+        
+            let password_hash = argon2
+                .hash_password(req.password.expose_secret().as_bytes(), &salt_string)
+                .map_err(|e| CryptoError::Argon2Error(e.to_string()))?;
+
+            Ok(KdfResponse {
+                version: 1,
+                hash: password_hash.to_string().into(),
+                salt: general_purpose::STANDARD.encode(&salt_bytes),
+                memory,
+                iterations,
+                parallelism,
+            })
+        })
+    }
+
+    pub fn aead_encrypt(&self, req: AeadEncryptRequest) -> Result<AeadEncryptResponse, CryptoError> {
+        events::observe("aead_encrypt", events::key_id(req.key.expose_secret()), || {
+            self.aead_seal(req.plaintext.expose_secret().as_bytes(), &req.key, req.additional_data.as_deref())
+        })
+    }
+
+    /// [`Self::aead_encrypt`] for binary contents.
+    pub fn aead_seal(
+        &self,
+        plaintext: &[u8],
+        key: &Secret,
+        additional_data: Option<&str>,
+    ) -> Result<AeadEncryptResponse, CryptoError> {
+        events::observe("aead_encrypt", events::key_id(key.expose_secret()), || {
+            let key_bytes = Zeroizing::new(general_purpose::STANDARD.decode(key.expose_secret())?);
+            if key_bytes.len() != xchacha20poly1305_ietf::KEYBYTES {
+                return Err(CryptoError::InvalidInput(
+                    format!("Key must be {} bytes", xchacha20poly1305_ietf::KEYBYTES)
+                ));
+            }
+
+            let key = xchacha20poly1305_ietf::Key::from_slice(&key_bytes)
+                .ok_or_else(|| CryptoError::InvalidInput("Invalid key format".to_string()))?;
+        
+            let nonce = xchacha20poly1305_ietf::gen_nonce();
+            let additional_data = additional_data.unwrap_or("");
+        
+            let ciphertext = xchacha20poly1305_ietf::seal(
+                plaintext,
+                Some(additional_data.as_bytes()),
+                &nonce,
+                &key,
+            );
+
+            Ok(AeadEncryptResponse {
+                version: 1,
+                ciphertext: general_purpose::STANDARD.encode(&ciphertext),
+                nonce: general_purpose::STANDARD.encode(nonce.0),
+            })
+        })
+    }
+
+    pub fn aead_decrypt(&self, req: AeadDecryptRequest) -> Result<AeadDecryptResponse, CryptoError> {
+        events::observe("aead_decrypt", events::key_id(req.key.expose_secret()), || {
+            let mut plaintext = self.aead_open(req)?;
+
+            let plaintext_str = String::from_utf8(std::mem::take(&mut *plaintext)).map_err(|e| {
+                drop(Zeroizing::new(e.into_bytes()));
+                CryptoError::DecryptionFailed("Invalid UTF-8 in plaintext".to_string())
+            })?;
+
+            Ok(AeadDecryptResponse {
+                version: 1,
+                plaintext: plaintext_str.into(),
+            })
+        })
+    }
+
+    /// [`Self::aead_decrypt`] for binary contents: the plaintext as raw bytes.
+    pub fn aead_open(&self, req: AeadDecryptRequest) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        events::observe("aead_decrypt", events::key_id(req.key.expose_secret()), || {
+            let key_bytes = Zeroizing::new(general_purpose::STANDARD.decode(req.key.expose_secret())?);
+            let ciphertext_bytes = general_purpose::STANDARD.decode(&req.ciphertext)?;
+            let nonce_bytes = general_purpose::STANDARD.decode(&req.nonce)?;
+
+            if key_bytes.len() != xchacha20poly1305_ietf::KEYBYTES {
+                return Err(CryptoError::InvalidInput(
+                    format!("Key must be {} bytes", xchacha20poly1305_ietf::KEYBYTES)
+                ));
+            }
+
+            if nonce_bytes.len() != xchacha20poly1305_ietf::NONCEBYTES {
+                return Err(CryptoError::InvalidInput(
+                    format!("Nonce must be {} bytes", xchacha20poly1305_ietf::NONCEBYTES)
+                ));
+            }
+
+            let key = xchacha20poly1305_ietf::Key::from_slice(&key_bytes)
+                .ok_or_else(|| CryptoError::InvalidInput("Invalid key format".to_string()))?;
+        
+            let nonce = xchacha20poly1305_ietf::Nonce::from_slice(&nonce_bytes)
+                .ok_or_else(|| CryptoError::InvalidInput("Invalid nonce format".to_string()))?;
+        
+            let additional_data = req.additional_data.as_deref().unwrap_or("");
+        
+            let plaintext = xchacha20poly1305_ietf::open(
+                &ciphertext_bytes,
+                Some(additional_data.as_bytes()),
+                &nonce,
+                &key,
+            ).map_err(|_| CryptoError::DecryptionFailed("Failed to decrypt".to_string()))?;
+
+            Ok(Zeroizing::new(plaintext))
+        })
+    }
+
+    pub fn key_wrap(&self, req: KeyWrapRequest) -> Result<KeyWrapResponse, CryptoError> {
+        events::observe("key_wrap", events::key_id(req.master_key.expose_secret()), || {
+            let master_key = Zeroizing::new(general_purpose::STANDARD.decode(req.master_key.expose_secret())?);
+            let user_key = Zeroizing::new(general_purpose::STANDARD.decode(req.user_key.expose_secret())?);
+        
+            let mut salt = [0u8; 32];
+            OsRng.fill_bytes(&mut salt);
+        
+            // Use HKDF to combine master key and user key
+            let hk = Hkdf::<Sha256>::new(Some(&salt), &master_key);
+            let mut derived_key = Zeroizing::new([0u8; 32]);
+            hk.expand(b"key-wrap", derived_key.as_mut())
+                .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+        
+            let key = xchacha20poly1305_ietf::Key::from_slice(derived_key.as_ref())
+                .ok_or_else(|| CryptoError::InvalidInput("Invalid derived key".to_string()))?;
+        
+            let nonce = xchacha20poly1305_ietf::gen_nonce();
+            let wrapped = xchacha20poly1305_ietf::seal(&user_key, None, &nonce, &key);
+        
+            // Combine nonce + wrapped key for storage
+            let mut result = Vec::new();
+            result.extend_from_slice(&nonce.0);
+            result.extend_from_slice(&wrapped);
+        
+            Ok(KeyWrapResponse {
+                version: 1,
+                wrapped_key: general_purpose::STANDARD.encode(&result),
+                salt: general_purpose::STANDARD.encode(salt),
+            })
+        })
+    }
+
+    pub fn key_unwrap(&self, req: KeyUnwrapRequest) -> Result<KeyUnwrapResponse, CryptoError> {
+        events::observe("key_unwrap", events::key_id(req.master_key.expose_secret()), || {
+            let master_key = Zeroizing::new(general_purpose::STANDARD.decode(req.master_key.expose_secret())?);
+            let wrapped_data = general_purpose::STANDARD.decode(&req.wrapped_key)?;
+            let salt = general_purpose::STANDARD.decode(&req.salt)?;
+        
+            if wrapped_data.len() < xchacha20poly1305_ietf::NONCEBYTES {
+                return Err(CryptoError::InvalidInput("Wrapped key too short".to_string()));
+            }
+        
+            // Split nonce and ciphertext
+            let (nonce_bytes, ciphertext) = wrapped_data.split_at(xchacha20poly1305_ietf::NONCEBYTES);
+        
+            // Derive the same key using HKDF
+            let hk = Hkdf::<Sha256>::new(Some(&salt), &master_key);
+            let mut derived_key = Zeroizing::new([0u8; 32]);
+            hk.expand(b"key-wrap", derived_key.as_mut())
+                .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+        
+            let key = xchacha20poly1305_ietf::Key::from_slice(derived_key.as_ref())
+                .ok_or_else(|| CryptoError::InvalidInput("Invalid derived key".to_string()))?;
+        
+            let nonce = xchacha20poly1305_ietf::Nonce::from_slice(nonce_bytes)
+                .ok_or_else(|| CryptoError::InvalidInput("Invalid nonce".to_string()))?;
+        
+            let unwrapped = xchacha20poly1305_ietf::open(ciphertext, None, &nonce, &key)
+                .map(Zeroizing::new)
+                .map_err(|_| CryptoError::DecryptionFailed("Failed to unwrap key".to_string()))?;
+        
+            Ok(KeyUnwrapResponse {
+                version: 1,
+                unwrapped_key: general_purpose::STANDARD.encode(&unwrapped).into(),
+            })
+        })
+    }
+
+    pub fn shamir_split(&self, req: ShamirSplitRequest) -> Result<ShamirSplitResponse, CryptoError> {
+        events::observe("shamir_split", None, || {
+            let secret = Zeroizing::new(general_purpose::STANDARD.decode(req.secret.expose_secret())?);
+            let shares = shamir::split(&secret, req.threshold, req.shares)?;
+
+            Ok(ShamirSplitResponse {
+                version: 1,
+                threshold: req.threshold,
+                shares: shares
+                    .iter()
+                    .map(|share| general_purpose::STANDARD.encode(share.to_bytes()).into())
+                    .collect(),
+            })
+        })
+    }
+
+    pub fn shamir_combine(&self, req: ShamirCombineRequest) -> Result<ShamirCombineResponse, CryptoError> {
+        events::observe("shamir_combine", None, || {
+            let shares = req
+                .shares
+                .iter()
+                .map(|share| {
+                    let bytes = Zeroizing::new(general_purpose::STANDARD.decode(share.expose_secret())?);
+                    shamir::Share::from_bytes(&bytes)
+                })
+                .collect::<Result<Vec<_>, CryptoError>>()?;
+            let secret = shamir::combine(&shares)?;
+
+            Ok(ShamirCombineResponse {
+                version: 1,
+                secret: general_purpose::STANDARD.encode(&secret).into(),
+            })
+        })
+    }
+
+    /// Generates an X25519 keypair for a beneficiary to receive sealed boxes.
+    pub fn box_keypair(&self) -> Result<BoxKeypairResponse, CryptoError> {
+        events::observe("box_keypair", None, || {
+            let (public_key, secret_key) = curve25519xsalsa20poly1305::gen_keypair();
+
+            Ok(BoxKeypairResponse {
+                version: 1,
+                public_key: general_purpose::STANDARD.encode(public_key.0),
+                secret_key: general_purpose::STANDARD.encode(secret_key.0).into(),
+            })
+        })
+    }
+
+    pub fn box_seal(&self, req: BoxSealRequest) -> Result<BoxSealResponse, CryptoError> {
+        events::observe("box_seal", events::key_id(&req.public_key), || {
+            let public_key_bytes = general_purpose::STANDARD.decode(&req.public_key)?;
+            let public_key = curve25519xsalsa20poly1305::PublicKey::from_slice(&public_key_bytes)
+                .ok_or_else(|| CryptoError::InvalidInput(
+                    format!("Public key must be {} bytes", curve25519xsalsa20poly1305::PUBLICKEYBYTES)
+                ))?;
+
+            let ciphertext = curve25519xsalsa20poly1305::seal(req.plaintext.expose_secret().as_bytes(), &public_key);
+
+            Ok(BoxSealResponse {
+                version: 1,
+                ciphertext: general_purpose::STANDARD.encode(&ciphertext),
+            })
+        })
+    }
+
+    pub fn box_open(&self, req: BoxOpenRequest) -> Result<BoxOpenResponse, CryptoError> {
+        events::observe("box_open", x25519_key_id(req.secret_key.expose_secret()), || {
+            let secret_key_bytes = Zeroizing::new(general_purpose::STANDARD.decode(req.secret_key.expose_secret())?);
+            let ciphertext = general_purpose::STANDARD.decode(&req.ciphertext)?;
+
+            let secret_key = curve25519xsalsa20poly1305::SecretKey::from_slice(&secret_key_bytes)
+                .ok_or_else(|| CryptoError::InvalidInput(
+                    format!("Secret key must be {} bytes", curve25519xsalsa20poly1305::SECRETKEYBYTES)
+                ))?;
+            if ciphertext.len() < curve25519xsalsa20poly1305::SEALBYTES {
+                return Err(CryptoError::InvalidInput("Sealed box too short".to_string()));
+            }
+
+            let plaintext = curve25519xsalsa20poly1305::open(&ciphertext, &secret_key.public_key(), &secret_key)
+                .map_err(|_| CryptoError::DecryptionFailed("Failed to open sealed box".to_string()))?;
+
+            let plaintext_str = String::from_utf8(plaintext).map_err(|e| {
+                drop(Zeroizing::new(e.into_bytes()));
+                CryptoError::DecryptionFailed("Invalid UTF-8 in plaintext".to_string())
+            })?;
+
+            Ok(BoxOpenResponse {
+                version: 1,
+                plaintext: plaintext_str.into(),
+            })
+        })
+    }
+
+    /// Single-shot HPKE to `public_key`; auth mode when `sender_secret_key` is set.
+    pub fn hpke_seal(&self, req: HpkeSealRequest) -> Result<HpkeSealResponse, CryptoError> {
+        events::observe("hpke_seal", events::key_id(&req.public_key), || {
+            let public_key = hpke::decode_public_key(&req.public_key)?;
+            let sender = req
+                .sender_secret_key
+                .as_ref()
+                .map(|key| hpke::decode_secret_key(key.expose_secret()))
+                .transpose()?;
+            let info = req.info.as_deref().unwrap_or("");
+            let additional_data = req.additional_data.as_deref().unwrap_or("");
+
+            let (enc, mut context) = hpke::setup_sender(&public_key, info.as_bytes(), sender.as_ref())?;
+            let ciphertext = context.seal(additional_data.as_bytes(), req.plaintext.expose_secret().as_bytes())?;
+
+            Ok(HpkeSealResponse {
+                version: 1,
+                mode: if sender.is_some() { "auth" } else { "base" }.to_string(),
+                enc: general_purpose::STANDARD.encode(enc),
+                ciphertext: general_purpose::STANDARD.encode(&ciphertext),
+            })
+        })
+    }
+
+    pub fn hpke_open(&self, req: HpkeOpenRequest) -> Result<HpkeOpenResponse, CryptoError> {
+        events::observe("hpke_open", x25519_key_id(req.secret_key.expose_secret()), || {
+            let enc = hpke::decode_enc(&req.enc)?;
+            let ciphertext = general_purpose::STANDARD.decode(&req.ciphertext)?;
+            let secret_key = hpke::decode_secret_key(req.secret_key.expose_secret())?;
+            let sender = req.sender_public_key.as_deref().map(hpke::decode_public_key).transpose()?;
+            let info = req.info.as_deref().unwrap_or("");
+            let additional_data = req.additional_data.as_deref().unwrap_or("");
+
+            let mut context = hpke::setup_receiver(&enc, &secret_key, info.as_bytes(), sender.as_ref())?;
+            let plaintext = context.open(additional_data.as_bytes(), &ciphertext)?;
+
+            let plaintext_str = String::from_utf8(plaintext).map_err(|e| {
+                drop(Zeroizing::new(e.into_bytes()));
+                CryptoError::DecryptionFailed("Invalid UTF-8 in plaintext".to_string())
+            })?;
+
+            Ok(HpkeOpenResponse {
+                version: 1,
+                plaintext: plaintext_str.into(),
+            })
+        })
+    }
+
+    /// Encrypts `plaintext` once under a fresh content key and wraps that key
+    /// for every recipient.
+    pub fn envelope_seal(&self, req: EnvelopeSealRequest) -> Result<MultiRecipientEnvelope, CryptoError> {
+        events::observe("envelope_seal", None, || {
+            if req.recipients.is_empty() {
+                return Err(CryptoError::InvalidInput("An envelope needs at least one recipient".to_string()));
+            }
+            check_new_recipients(&[], &req.recipients)?;
+
+            let mut content_key = Zeroizing::new([0u8; recipients::CONTENT_KEY_BYTES]);
+            OsRng.fill_bytes(content_key.as_mut());
+            let key = xchacha20poly1305_ietf::Key::from_slice(content_key.as_ref())
+                .ok_or_else(|| CryptoError::InvalidInput("Invalid content key".to_string()))?;
+
+            let nonce = xchacha20poly1305_ietf::gen_nonce();
+            let additional_data = req.additional_data.as_deref().unwrap_or("");
+            let ciphertext = xchacha20poly1305_ietf::seal(
+                req.plaintext.expose_secret().as_bytes(),
+                Some(additional_data.as_bytes()),
+                &nonce,
+                &key,
+            );
+
+            Ok(MultiRecipientEnvelope {
+                version: 1,
+                nonce: general_purpose::STANDARD.encode(nonce.0),
+                ciphertext: general_purpose::STANDARD.encode(&ciphertext),
+                recipients: req
+                    .recipients
+                    .iter()
+                    .map(|recipient| recipients::wrap(recipient, &content_key))
+                    .collect::<Result<_, _>>()?,
+            })
+        })
+    }
+
+    pub fn envelope_open(&self, req: EnvelopeOpenRequest) -> Result<EnvelopeOpenResponse, CryptoError> {
+        events::observe("envelope_open", Some(req.identity.id().to_string()), || {
+            check_envelope_version(&req.envelope)?;
+            let content_key = recipients::unwrap(&req.envelope.recipients, &req.identity)?;
+            let key = xchacha20poly1305_ietf::Key::from_slice(content_key.as_ref())
+                .ok_or_else(|| CryptoError::InvalidInput("Invalid content key".to_string()))?;
+
+            let nonce = xchacha20poly1305_ietf::Nonce::from_slice(&general_purpose::STANDARD.decode(&req.envelope.nonce)?)
+                .ok_or_else(|| CryptoError::InvalidInput(
+                    format!("Nonce must be {} bytes", xchacha20poly1305_ietf::NONCEBYTES)
+                ))?;
+            let ciphertext = general_purpose::STANDARD.decode(&req.envelope.ciphertext)?;
+            let additional_data = req.additional_data.as_deref().unwrap_or("");
+
+            let plaintext = xchacha20poly1305_ietf::open(&ciphertext, Some(additional_data.as_bytes()), &nonce, &key)
+                .map_err(|_| CryptoError::DecryptionFailed("Failed to decrypt".to_string()))?;
+
+            let plaintext_str = String::from_utf8(plaintext).map_err(|e| {
+                drop(Zeroizing::new(e.into_bytes()));
+                CryptoError::DecryptionFailed("Invalid UTF-8 in plaintext".to_string())
+            })?;
+
+            Ok(EnvelopeOpenResponse {
+                version: 1,
+                plaintext: plaintext_str.into(),
+            })
+        })
+    }
+
+    /// Wraps the content key, recovered with `identity`, for more recipients.
+    pub fn envelope_add_recipients(
+        &self,
+        req: EnvelopeAddRecipientsRequest,
+    ) -> Result<MultiRecipientEnvelope, CryptoError> {
+        events::observe("envelope_add_recipients", Some(req.identity.id().to_string()), || {
+            check_envelope_version(&req.envelope)?;
+            check_new_recipients(&req.envelope.recipients, &req.recipients)?;
+            let content_key = recipients::unwrap(&req.envelope.recipients, &req.identity)?;
+
+            let mut envelope = req.envelope;
+            for recipient in &req.recipients {
+                envelope.recipients.push(recipients::wrap(recipient, &content_key)?);
+            }
+            Ok(envelope)
+        })
+    }
+
+    /// Drops the stanzas of `ids`; at least one recipient must remain.
+    pub fn envelope_remove_recipients(
+        &self,
+        req: EnvelopeRemoveRecipientsRequest,
+    ) -> Result<MultiRecipientEnvelope, CryptoError> {
+        events::observe("envelope_remove_recipients", None, || {
+            check_envelope_version(&req.envelope)?;
+            let mut envelope = req.envelope;
+            for id in &req.ids {
+                if !envelope.recipients.iter().any(|stanza| stanza.id() == id) {
+                    return Err(CryptoError::InvalidInput(format!("No recipient with id {:?}", id)));
+                }
+            }
+            envelope.recipients.retain(|stanza| !req.ids.iter().any(|id| id == stanza.id()));
+            if envelope.recipients.is_empty() {
+                return Err(CryptoError::InvalidInput("An envelope needs at least one recipient".to_string()));
+            }
+            Ok(envelope)
+        })
+    }
+
+    /// Generates an X25519 identity for age files.
+    pub fn age_keygen(&self) -> Result<AgeKeygenResponse, CryptoError> {
+        events::observe("age_keygen", None, || {
+            let (recipient, identity) = age::generate_identity();
+            Ok(AgeKeygenResponse {
+                version: 1,
+                recipient,
+                identity: identity.as_str().into(),
+            })
+        })
+    }
+
+    /// Encrypts to an age file that the `age` CLI can decrypt.
+    pub fn age_encrypt(&self, req: AgeEncryptRequest) -> Result<AgeEncryptResponse, CryptoError> {
+        events::observe("age_encrypt", None, || {
+            let file = age::encrypt(req.plaintext.expose_secret().as_bytes(), &req.recipients)?;
+            Ok(AgeEncryptResponse {
+                version: 1,
+                file: if req.armor.unwrap_or(true) {
+                    age::armor(&file)
+                } else {
+                    general_purpose::STANDARD.encode(&file)
+                },
+            })
+        })
+    }
+
+    pub fn age_decrypt(&self, req: AgeDecryptRequest) -> Result<AgeDecryptResponse, CryptoError> {
+        events::observe("age_decrypt", age_key_id(&req.identity), || {
+            let file = if age::is_armored(&req.file) {
+                age::dearmor(&req.file)?
+            } else {
+                general_purpose::STANDARD.decode(req.file.trim())?
+            };
+            let mut plaintext = age::decrypt(&file, &req.identity)?;
+
+            let plaintext_str = String::from_utf8(std::mem::take(&mut *plaintext)).map_err(|e| {
+                drop(Zeroizing::new(e.into_bytes()));
+                CryptoError::DecryptionFailed("Invalid UTF-8 in plaintext".to_string())
+            })?;
+
+            Ok(AgeDecryptResponse {
+                version: 1,
+                plaintext: plaintext_str.into(),
+            })
+        })
+    }
+
+    /// Generates a signing key inside the boundary and makes it the active key.
+    pub fn generate_signing_key(&self) -> Result<Jwk, CryptoError> {
+        events::observe("signing_key_generate", None, || {
+            Ok(signing::jwk(&self.signing_keys.generate(&self.key_store)?))
+        })
+    }
+
+    /// Imports a 32-byte Ed25519 seed, e.g. to keep one key across restarts,
+    /// and makes it the active key. The caller wipes its copy.
+    pub fn import_signing_key(&self, seed: &[u8]) -> Result<Jwk, CryptoError> {
+        events::observe("signing_key_import", None, || {
+            Ok(signing::jwk(&self.signing_keys.import(&self.key_store, seed)?))
+        })
+    }
+
+/// Publishes the keys of an earlier JWK Set, such as a saved
+    /// `GET /keys/signing`, as retired: they verify old signatures but never sign.
+    pub fn retire_signing_keys(&self, keys: &[Jwk]) -> Result<(), CryptoError> {
+        events::observe("signing_keys_retire", None, || {
+            for jwk in keys {
+                self.signing_keys.retire(signing::from_jwk(jwk)?)?;
+            }
+            Ok(())
+        })
+    }
+
+        pub fn signing_keys(&self) -> Result<SigningKeysResponse, CryptoError> {
+        events::observe("signing_keys", None, || {
+            Ok(SigningKeysResponse {
+                keys: self.signing_keys.public_keys()?.iter().map(signing::jwk).collect(),
+            })
+        })
+    }
+
+    /// Signs in a caller's context; the service's own contexts are refused.
+    pub fn sign(&self, req: SignRequest) -> Result<SignResponse, CryptoError> {
+        events::observe("sign", req.kid.clone(), || {
+            signing::check_caller_context(&req.context)?;
+            let (kid, signature) = self.signing_keys.sign(
+                &self.key_store,
+                req.kid.as_deref(),
+                &req.context,
+                req.message.as_bytes(),
+            )?;
+            Ok(SignResponse {
+                version: 1,
+                kid,
+                context: req.context,
+                signature: general_purpose::STANDARD.encode(signature.to_bytes()),
+            })
+        })
+    }
+
+    /// A well-formed signature that does not verify is `valid: false`, not an error.
+    pub fn verify(&self, req: VerifyRequest) -> Result<VerifyResponse, CryptoError> {
+        events::observe("verify", req.kid.clone(), || {
+            let public_key = match (&req.public_key, &req.kid) {
+                (Some(public_key), _) => signing::decode_public_key(public_key)?,
+                (None, Some(kid)) => self
+                    .signing_keys
+                    .get(kid)?
+                    .ok_or_else(|| CryptoError::InvalidInput(format!("Unknown signing key {:?}", kid)))?,
+                (None, None) => return Err(CryptoError::InvalidInput("Either kid or public_key is required".to_string())),
+            };
+            let kid = signing::key_id(&public_key);
+            if req.kid.as_ref().is_some_and(|requested| *requested != kid) {
+                return Err(CryptoError::InvalidInput("kid does not match public_key".to_string()));
+            }
+
+            let signature = signing::decode_signature(&req.signature)?;
+            Ok(VerifyResponse {
+                version: 1,
+                valid: signing::verify(&public_key, &req.context, req.message.as_bytes(), &signature)?,
+                kid,
+            })
+        })
+    }
+
+    /// Signs a release certificate with `kid` or the active signing key.
+    pub fn issue_release_certificate(&self, req: ReleaseCertificateRequest) -> Result<ReleaseCertificate, CryptoError> {
+        events::observe("release_certificate", None, || {
+            for (name, value) in [
+                ("will_id", &req.will_id),
+                ("release_event_id", &req.release_event_id),
+                ("trigger_reason", &req.trigger_reason),
+            ] {
+                if value.trim().is_empty() {
+                    return Err(CryptoError::InvalidInput(format!("{} must not be empty", name)));
+                }
+            }
+            if req.approvals.iter().any(|approval| approval.approved_by.trim().is_empty()) {
+                return Err(CryptoError::InvalidInput("approved_by must not be empty".to_string()));
+            }
+            certificate::check_content_hash(&req.content_hash)?;
+
+            let attestation = ReleaseAttestation {
+                version: certificate::CERTIFICATE_VERSION,
+                will_id: req.will_id,
+                release_event_id: req.release_event_id,
+                event_type: req.event_type,
+                trigger_reason: req.trigger_reason,
+                approvals: req.approvals,
+                released_at: req.released_at,
+                content_hash: req.content_hash,
+                issued_at: Utc::now().trunc_subsecs(0),
+                kid: self.signing_keys.resolve(req.kid.as_deref())?,
+            };
+            let (_, signature) = self.signing_keys.sign(
+                &self.key_store,
+                Some(&attestation.kid),
+                certificate::CONTEXT,
+                &canonical::to_vec(&attestation)?,
+            )?;
+            Ok(ReleaseCertificate {
+                attestation,
+                signature: general_purpose::STANDARD.encode(signature.to_bytes()),
+            })
+        })
+    }
+
+    /// Seals a released will's files into a [`ReleaseArchive`] for
+    /// `lastwords-open`, under a master key derived from a passphrase (with
+    /// the `kdf_argon2id` defaults) or generated and split into shares.
+    pub fn export_release_archive(&self, req: ReleaseArchiveRequest) -> Result<ReleaseArchiveResponse, CryptoError> {
+        events::observe("release_archive", None, || {
+            if req.will_id.trim().is_empty() {
+                return Err(CryptoError::InvalidInput("will_id must not be empty".to_string()));
+            }
+            let items = req
+                .items
+                .iter()
+                .map(|item| {
+                    let contents = Zeroizing::new(general_purpose::STANDARD.decode(item.contents.expose_secret())?);
+                    Ok((item.name.clone(), contents))
+                })
+                .collect::<Result<Vec<_>, CryptoError>>()?;
+
+            let (unlock, master_key, shares) = match req.protection {
+                ArchiveProtection::Passphrase { passphrase } => {
+                    let mut salt = [0u8; 32];
+                    OsRng.fill_bytes(&mut salt);
+                    let unlock = archive::Unlock::Passphrase {
+                        salt: general_purpose::STANDARD.encode(salt),
+                        memory: 65536,
+                        iterations: 3,
+                        parallelism: 1,
+                    };
+                    let master_key = archive::passphrase_key(self, &passphrase, &unlock)?;
+                    (unlock, master_key, None)
+                }
+                ArchiveProtection::Shares { threshold, shares } => {
+                    let master_key = Zeroizing::new(xchacha20poly1305_ietf::gen_key().0.to_vec());
+                    let split = self.shamir_split(ShamirSplitRequest {
+                        secret: general_purpose::STANDARD.encode(master_key.as_slice()).into(),
+                        threshold,
+                        shares,
+                    })?;
+                    (archive::Unlock::Shares { threshold }, master_key, Some(split.shares))
+                }
+            };
+
+            Ok(ReleaseArchiveResponse {
+                version: 1,
+                archive: ReleaseArchive::seal(self, &req.will_id, unlock, &master_key, &items)?,
+                shares,
+            })
+        })
+    }
+
+    pub fn verify_release_certificate(
+        &self,
+        req: VerifyReleaseCertificateRequest,
+    ) -> Result<VerifyReleaseCertificateResponse, CryptoError> {
+        events::observe("release_certificate_verify", None, || {
+            let kid = &req.certificate.attestation.kid;
+            let public_key = self.signed_by(kid, req.public_key.as_deref())?;
+
+            Ok(VerifyReleaseCertificateResponse {
+                version: 1,
+                valid: certificate::verify(&req.certificate, &public_key)?,
+                kid: kid.clone(),
+                content_matches: req
+                    .encrypted_content
+                    .as_deref()
+                    .map(|content| certificate::content_matches(&req.certificate, content))
+                    .transpose()?,
+            })
+        })
+    }
+
+    /// Appends `event` to the audit chain, stamped with the current time.
+    pub fn audit_append(&self, event: AuditEvent) -> Result<AuditAppendResponse, CryptoError> {
+        events::observe("audit_append", None, || {
+            let record = self.audit_log.append(event, Utc::now().trunc_subsecs(3))?;
+            Ok(AuditAppendResponse { version: 1, sequence: record.sequence, head: record.hash })
+        })
+    }
+
+    /// Keeps the audit log in `path` from now on, after loading what is there.
+    pub fn open_audit_log(&self, path: &std::path::Path) -> Result<u64, CryptoError> {
+        self.audit_log.open_file(path)
+    }
+
+    pub fn audit_log(&self) -> Result<AuditLogResponse, CryptoError> {
+        events::observe("audit_log", None, || {
+            Ok(AuditLogResponse { version: 1, records: self.audit_log.records()? })
+        })
+    }
+
+    /// Checks an exported chain; a break is a result, not an error.
+    pub fn verify_audit_chain(&self, req: AuditVerifyRequest) -> Result<AuditVerifyResponse, CryptoError> {
+        events::observe("audit_verify", None, || {
+            let first_break = auditlog::verify_chain(&req.records).err();
+            Ok(AuditVerifyResponse {
+                version: 1,
+                valid: first_break.is_none(),
+                length: req.records.len(),
+                head: match first_break {
+                    None => req.records.last().map(|record| record.hash.clone()),
+                    Some(_) => None,
+                },
+                first_break,
+            })
+        })
+    }
+
+    /// Signs a tree head over the whole audit log, unless the latest one
+    /// already covers it.
+    pub fn audit_checkpoint(&self) -> Result<SignedTreeHead, CryptoError> {
+        events::observe("audit_checkpoint", None, || {
+            let (tree_size, root) = self.audit_log.root()?;
+            if let Some(latest) = self.audit_log.tree_head()? {
+                if latest.tree_head.tree_size == tree_size {
+                    return Ok(latest);
+                }
+            }
+
+            let tree_head = AuditTreeHead {
+                version: auditlog::TREE_HEAD_VERSION,
+                tree_size,
+                root_hash: hex::encode(root),
+                timestamp: Utc::now().trunc_subsecs(3),
+                kid: self.signing_keys.resolve(None)?,
+                log_id: if tree_size == 0 { None } else { self.audit_log.log_id()? },
+            };
+            let (_, signature) = self.signing_keys.sign(
+                &self.key_store,
+                Some(&tree_head.kid),
+                auditlog::TREE_HEAD_CONTEXT,
+                &canonical::to_vec(&tree_head)?,
+            )?;
+            let signed = SignedTreeHead { tree_head, signature: general_purpose::STANDARD.encode(signature.to_bytes()) };
+            self.audit_log.set_tree_head(signed.clone())?;
+            Ok(signed)
+        })
+    }
+
+    /// The most recent signed tree head.
+    pub fn audit_tree_head(&self) -> Result<SignedTreeHead, CryptoError> {
+        events::observe("audit_tree_head", None, || {
+            self.audit_log
+                .tree_head()?
+                .ok_or_else(|| CryptoError::InvalidInput("No tree head has been signed yet".to_string()))
+        })
+    }
+
+    pub fn audit_inclusion_proof(
+        &self,
+        req: AuditInclusionProofRequest,
+    ) -> Result<AuditInclusionProofResponse, CryptoError> {
+        events::observe("audit_inclusion_proof", None, || {
+            let (tree_size, leaf, path) = self.audit_log.inclusion_proof(req.sequence, req.tree_size)?;
+            Ok(AuditInclusionProofResponse {
+                version: 1,
+                sequence: req.sequence,
+                tree_size,
+                leaf_hash: hex::encode(leaf),
+                audit_path: path.iter().map(hex::encode).collect(),
+            })
+        })
+    }
+
+    pub fn audit_consistency_proof(
+        &self,
+        req: AuditConsistencyProofRequest,
+    ) -> Result<AuditConsistencyProofResponse, CryptoError> {
+        events::observe("audit_consistency_proof", None, || {
+            let (second, proof) = self.audit_log.consistency_proof(req.first, req.second)?;
+            Ok(AuditConsistencyProofResponse {
+                version: 1,
+                first: req.first,
+                second,
+                proof: proof.iter().map(hex::encode).collect(),
+            })
+        })
+    }
+
+    pub fn verify_audit_inclusion(
+        &self,
+        req: VerifyAuditInclusionRequest,
+    ) -> Result<VerifyAuditInclusionResponse, CryptoError> {
+        events::observe("audit_verify_inclusion", None, || {
+            let kid = &req.tree_head.tree_head.kid;
+            let public_key = self.signed_by(kid, req.public_key.as_deref())?;
+
+            let valid = auditlog::verify_tree_head(&req.tree_head, &public_key)?
+                && auditlog::verify_inclusion(&req.record, &req.tree_head.tree_head, &req.audit_path)?;
+            Ok(VerifyAuditInclusionResponse { version: 1, valid, kid: kid.clone() })
+        })
+    }
+
+    /// Brings each check up to date, records every trigger in the audit log
+    /// and keeps the rows for [`Self::evaluate_stored_alive_checks`].
+    pub fn evaluate_alive_checks(
+        &self,
+        req: EvaluateAliveChecksRequest,
+    ) -> Result<EvaluateAliveChecksResponse, CryptoError> {
+        events::observe("alive_checks_evaluate", None, || {
+            let mut checks = req.checks;
+            let mut events = Vec::new();
+            for check in &mut checks {
+                events.extend(deadman::evaluate(check, &SystemClock)?);
+            }
+            self.audit_triggers(&events)?;
+            self.store_alive_checks(&checks)?;
+            Ok(EvaluateAliveChecksResponse { version: 1, checks, events })
+        })
+    }
+
+    pub fn confirm_alive_check(&self, req: ConfirmAliveCheckRequest) -> Result<ConfirmAliveCheckResponse, CryptoError> {
+        events::observe("alive_checks_confirm", None, || {
+            let mut check = req.check;
+            let (confirmed, events) = deadman::confirm(&mut check, &SystemClock)?;
+            self.audit_triggers(&events)?;
+            self.store_alive_checks(std::slice::from_ref(&check))?;
+            Ok(ConfirmAliveCheckResponse { version: 1, confirmed, check, events })
+        })
+    }
+
+    /// Evaluates every stored check against `clock`, as the scheduler does
+    /// between the API's calls, and records the triggers.
+    pub fn evaluate_stored_alive_checks(&self, clock: &dyn Clock) -> Result<Vec<AliveCheckEvent>, CryptoError> {
+        events::observe("alive_checks_evaluate", None, || {
+            let mut checks = self
+                .alive_checks
+                .write()
+                .map_err(|_| CryptoError::SecureMemory("Alive check lock poisoned".to_string()))?;
+            let mut events = Vec::new();
+            for check in checks.values_mut().filter(|check| check.is_active) {
+                events.extend(deadman::evaluate(check, clock)?);
+            }
+            self.audit_triggers(&events)?;
+            Ok(events)
+        })
+    }
+
+    fn store_alive_checks(&self, checks: &[AliveCheck]) -> Result<(), CryptoError> {
+        let mut stored = self
+            .alive_checks
+            .write()
+            .map_err(|_| CryptoError::SecureMemory("Alive check lock poisoned".to_string()))?;
+        for check in checks {
+            stored.insert(check.id.clone(), check.clone());
+        }
+        Ok(())
+    }
+
+    /// Logs each trigger once per check, however often a stale or retried row
+    /// triggers it again.
+    fn audit_triggers(&self, events: &[AliveCheckEvent]) -> Result<(), CryptoError> {
+        for event in events {
+            if let AliveCheckEvent::Triggered { check_id, user_id, trigger_reason, triggered_at, .. } = event {
+                let details = serde_json::json!({ "check_id": check_id, "reason": trigger_reason });
+                self.audit_log.append_unless(
+                    AuditEvent {
+                        operation: "dead_mans_switch".to_string(),
+                        resource: "alive_check".to_string(),
+                        action: "trigger".to_string(),
+                        result: "success".to_string(),
+                        user_id: Some(user_id.clone()),
+                        session_id: None,
+                        details: Some(details),
+                    },
+                    triggered_at.trunc_subsecs(3),
+                    |record| {
+                        record.event.operation == "dead_mans_switch"
+                            && record.event.action == "trigger"
+                            && record.event.details.as_ref().and_then(|details| details.get("check_id"))
+                                == Some(&serde_json::Value::from(check_id.as_str()))
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The key a signed statement names as `kid`: `public_key` if given,
+    /// otherwise the service's own key.
+    fn signed_by(&self, kid: &str, public_key: Option<&str>) -> Result<VerifyingKey, CryptoError> {
+        let public_key = match public_key {
+            Some(public_key) => signing::decode_public_key(public_key)?,
+            None => self
+                .signing_keys
+                .get(kid)?
+                .ok_or_else(|| CryptoError::InvalidInput(format!("Unknown signing key {:?}", kid)))?,
+        };
+        if signing::key_id(&public_key) != kid {
+            return Err(CryptoError::InvalidInput("kid does not match public_key".to_string()));
+        }
+        Ok(public_key)
+    }
+}
+
+/// The logged id of the X25519 key pair a base64 secret key belongs to.
+fn x25519_key_id(secret_key: &str) -> Option<String> {
+    let bytes = Zeroizing::new(general_purpose::STANDARD.decode(secret_key).ok()?);
+    let secret = x25519_dalek::StaticSecret::from(<[u8; 32]>::try_from(bytes.as_slice()).ok()?);
+    Some(events::raw_key_id(x25519_dalek::PublicKey::from(&secret).as_bytes()))
+}
+
+/// The logged id of an age X25519 identity; passphrases get none.
+fn age_key_id(identity: &AgeIdentity) -> Option<String> {
+    match identity {
+        AgeIdentity::X25519 { identity } => {
+            let secret = age::parse_identity(identity.expose_secret()).ok()?;
+            Some(events::raw_key_id(x25519_dalek::PublicKey::from(&secret).as_bytes()))
+        }
+        AgeIdentity::Scrypt { .. } => None,
+    }
+}
+
+fn check_envelope_version(envelope: &MultiRecipientEnvelope) -> Result<(), CryptoError> {
+    if envelope.version != 1 {
+        return Err(CryptoError::InvalidInput(format!("Unsupported envelope version {}", envelope.version)));
+    }
+    Ok(())
+}
+
+/// Recipient ids must be unique within an envelope.
+fn check_new_recipients(existing: &[RecipientStanza], new: &[Recipient]) -> Result<(), CryptoError> {
+    for (i, recipient) in new.iter().enumerate() {
+        let id = recipient.id();
+        if existing.iter().any(|stanza| stanza.id() == id) || new[..i].iter().any(|r| r.id() == id) {
+            return Err(CryptoError::InvalidInput(format!("Duplicate recipient id {:?}", id)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose;
+    use secrecy::ExposeSecret;
+
+    #[test]
+    fn test_kdf_argon2id() {
+        let service = CryptoBoundaryService::new();
+        let req = KdfRequest {
+            password: "test_password".into(),
+            salt: None,
+            memory: Some(4096),
+            iterations: Some(3),
+            parallelism: Some(1),
+        };
+        
+        let result = service.kdf_argon2id(req).unwrap();
+        assert_eq!(result.version, 1);
+        assert_eq!(result.memory, 4096);
+        assert_eq!(result.iterations, 3);
+        assert_eq!(result.parallelism, 1);
+        assert!(!result.hash.expose_secret().is_empty());
+        assert!(!result.salt.is_empty());
+    }
+
+    #[test]
+    fn test_aead_encrypt_decrypt() {
+        let service = CryptoBoundaryService::new();
+        
+        // Generate a random key
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        let key_b64 = general_purpose::STANDARD.encode(key);
+        
+        let plaintext = "Hello, World! This is a test message.";
+        
+        let encrypt_req = AeadEncryptRequest {
+            plaintext: plaintext.into(),
+            key: key_b64.clone().into(),
+            additional_data: Some("test_aad".to_string()),
+        };
+        
+        let encrypted = service.aead_encrypt(encrypt_req).unwrap();
+        assert_eq!(encrypted.version, 1);
+        assert!(!encrypted.ciphertext.is_empty());
+        assert!(!encrypted.nonce.is_empty());
+        
+        let decrypt_req = AeadDecryptRequest {
+            ciphertext: encrypted.ciphertext,
+            key: key_b64.into(),
+            nonce: encrypted.nonce,
+            additional_data: Some("test_aad".to_string()),
+        };
+        
+        let decrypted = service.aead_decrypt(decrypt_req).unwrap();
+        assert_eq!(decrypted.version, 1);
+        assert_eq!(decrypted.plaintext.expose_secret(), plaintext);
+    }
+
+    #[test]
+    fn test_key_wrap_unwrap() {
+        let service = CryptoBoundaryService::new();
+        
+        // Generate random keys
+        let mut master_key = [0u8; 32];
+        let mut user_key = [0u8; 32];
+        OsRng.fill_bytes(&mut master_key);
+        OsRng.fill_bytes(&mut user_key);
+        
+        let master_key_b64 = general_purpose::STANDARD.encode(master_key);
+        let user_key_b64 = general_purpose::STANDARD.encode(user_key);
+        
+        let wrap_req = KeyWrapRequest {
+            master_key: master_key_b64.clone().into(),
+            user_key: user_key_b64.clone().into(),
+        };
+        
+        let wrapped = service.key_wrap(wrap_req).unwrap();
+        assert_eq!(wrapped.version, 1);
+        assert!(!wrapped.wrapped_key.is_empty());
+        assert!(!wrapped.salt.is_empty());
+        
+        let unwrap_req = KeyUnwrapRequest {
+            master_key: master_key_b64.into(),
+            wrapped_key: wrapped.wrapped_key,
+            salt: wrapped.salt,
+        };
+        
+        let unwrapped = service.key_unwrap(unwrap_req).unwrap();
+        assert_eq!(unwrapped.version, 1);
+        assert_eq!(unwrapped.unwrapped_key.expose_secret(), user_key_b64);
+    }
+
+    #[test]
+    fn test_known_vectors_argon2id() {
+        let service = CryptoBoundaryService::new();
+        
+        // Test with known salt for reproducible results
+        let salt = "c2FsdDEyMzQ1Njc4OTBhYmNkZWZnaGlqa2xtbm9wcXJzdHV2d3h5ejEyMzQ1Ng=="; // "salt1234567890abcdefghijklmnopqrstuvwxyz123456" in base64
+        
+        let req = KdfRequest {
+            password: "password123".into(),
+            salt: Some(salt.to_string()),
+            memory: Some(4096),
+            iterations: Some(3),
+            parallelism: Some(1),
+        };
+        
+        let result = service.kdf_argon2id(req).unwrap();
+        assert_eq!(result.version, 1);
+        assert_eq!(result.salt, salt);
+        
+        // Frozen v1 output; tests/vectors.rs covers the published vectors
+        assert_eq!(
+            result.hash.expose_secret(),
+            "$argon2id$v=19$m=4096,t=3,p=1$c2FsdDEyMzQ1Njc4OTBhYmNkZWZnaGlqa2xtbm9wcXJzdHV2d3h5ejEyMzQ1Ng$HU1LY0rmPFdOuDrSu9SU1i5a3p1vt7E/KDeSV0EOjTs"
+        );
+    }
+
+    #[test]
+    fn test_aead_with_empty_additional_data() {
+        let service = CryptoBoundaryService::new();
+        
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        let key_b64 = general_purpose::STANDARD.encode(key);
+        
+        let plaintext = "Test without AAD";
+        
+        let encrypt_req = AeadEncryptRequest {
+            plaintext: plaintext.into(),
+            key: key_b64.clone().into(),
+            additional_data: None,
+        };
+        
+        let encrypted = service.aead_encrypt(encrypt_req).unwrap();
+        
+        let decrypt_req = AeadDecryptRequest {
+            ciphertext: encrypted.ciphertext,
+            key: key_b64.into(),
+            nonce: encrypted.nonce,
+            additional_data: None,
+        };
+        
+        let decrypted = service.aead_decrypt(decrypt_req).unwrap();
+        assert_eq!(decrypted.plaintext.expose_secret(), plaintext);
+    }
+
+    #[test]
+    fn test_invalid_key_size() {
+        let service = CryptoBoundaryService::new();
+        
+        let invalid_key = general_purpose::STANDARD.encode(b"short_key");
+        
+        let encrypt_req = AeadEncryptRequest {
+            plaintext: "test".into(),
+            key: invalid_key.into(),
+            additional_data: None,
+        };
+        
+        let result = service.aead_encrypt(encrypt_req);
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), CryptoError::InvalidInput(_)));
+    }
+
+    #[test]
+    fn test_box_seal_open() {
+        let service = CryptoBoundaryService::new();
+        let beneficiary = service.box_keypair().unwrap();
+
+        let sealed = service.box_seal(BoxSealRequest {
+            plaintext: "share for Alice".into(),
+            public_key: beneficiary.public_key.clone(),
+        }).unwrap();
+        assert_eq!(sealed.version, 1);
+
+        let opened = service.box_open(BoxOpenRequest {
+            ciphertext: sealed.ciphertext.clone(),
+            secret_key: beneficiary.secret_key,
+        }).unwrap();
+        assert_eq!(opened.plaintext.expose_secret(), "share for Alice");
+
+        // Another beneficiary's key cannot open it.
+        let other = service.box_keypair().unwrap();
+        let result = service.box_open(BoxOpenRequest {
+            ciphertext: sealed.ciphertext,
+            secret_key: other.secret_key,
+        });
+        assert!(matches!(result, Err(CryptoError::DecryptionFailed(_))));
+
+        let result = service.box_seal(BoxSealRequest {
+            plaintext: "x".into(),
+            public_key: general_purpose::STANDARD.encode([0u8; 16]),
+        });
+        assert!(matches!(result, Err(CryptoError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_hpke_seal_open_with_box_keys() {
+        let service = CryptoBoundaryService::new();
+        let beneficiary = service.box_keypair().unwrap();
+        let owner = service.box_keypair().unwrap();
+
+        for sender in [None, Some(owner.secret_key.clone())] {
+            let sealed = service.hpke_seal(HpkeSealRequest {
+                plaintext: "share for Bob".into(),
+                public_key: beneficiary.public_key.clone(),
+                info: Some("last-words release".to_string()),
+                additional_data: Some("will-42".to_string()),
+                sender_secret_key: sender.clone(),
+            }).unwrap();
+            assert_eq!(sealed.mode, if sender.is_some() { "auth" } else { "base" });
+
+            let open = |sender_public_key: Option<String>| service.hpke_open(HpkeOpenRequest {
+                enc: sealed.enc.clone(),
+                ciphertext: sealed.ciphertext.clone(),
+                secret_key: beneficiary.secret_key.clone(),
+                info: Some("last-words release".to_string()),
+                additional_data: Some("will-42".to_string()),
+                sender_public_key,
+            });
+            let expected_sender = sender.is_some().then(|| owner.public_key.clone());
+            assert_eq!(open(expected_sender.clone()).unwrap().plaintext.expose_secret(), "share for Bob");
+
+            // Opening in the other mode fails.
+            let wrong_sender = if expected_sender.is_some() { None } else { Some(owner.public_key.clone()) };
+            assert!(matches!(open(wrong_sender), Err(CryptoError::DecryptionFailed(_))));
+        }
+    }
+
+    #[test]
+    fn test_envelope_recipients() {
+        let service = CryptoBoundaryService::new();
+        let bob = service.box_keypair().unwrap();
+        let carol = service.box_keypair().unwrap();
+        let alice_key = general_purpose::STANDARD.encode([3u8; 32]);
+
+        let envelope = service.envelope_seal(EnvelopeSealRequest {
+            plaintext: "the safe code is 1234".into(),
+            additional_data: Some("will-42".to_string()),
+            recipients: vec![
+                Recipient::Symmetric { id: "alice".to_string(), key: alice_key.clone().into() },
+                Recipient::X25519 { id: "bob".to_string(), public_key: bob.public_key.clone() },
+            ],
+        }).unwrap();
+        assert_eq!(envelope.recipients.len(), 2);
+
+        let open = |envelope: &MultiRecipientEnvelope, identity: RecipientIdentity| service.envelope_open(
+            EnvelopeOpenRequest {
+                envelope: envelope.clone(),
+                identity,
+                additional_data: Some("will-42".to_string()),
+            },
+        );
+        let alice = RecipientIdentity::Symmetric { id: "alice".to_string(), key: alice_key.into() };
+        let bob_identity = RecipientIdentity::X25519 { id: "bob".to_string(), secret_key: bob.secret_key.clone() };
+        for identity in [alice.clone(), bob_identity.clone()] {
+            assert_eq!(open(&envelope, identity).unwrap().plaintext.expose_secret(), "the safe code is 1234");
+        }
+
+        // A wrong key for a known id, and an id that is not in the envelope.
+        let wrong = RecipientIdentity::X25519 { id: "bob".to_string(), secret_key: carol.secret_key.clone() };
+        assert!(matches!(open(&envelope, wrong), Err(CryptoError::DecryptionFailed(_))));
+        let carol_identity = RecipientIdentity::X25519 { id: "carol".to_string(), secret_key: carol.secret_key };
+        assert!(matches!(open(&envelope, carol_identity.clone()), Err(CryptoError::InvalidInput(_))));
+
+        // Adding Carol leaves the payload untouched.
+        let carol_recipient = Recipient::X25519 { id: "carol".to_string(), public_key: carol.public_key };
+        let added = service.envelope_add_recipients(EnvelopeAddRecipientsRequest {
+            envelope: envelope.clone(),
+            identity: alice.clone(),
+            recipients: vec![carol_recipient.clone()],
+        }).unwrap();
+        assert_eq!(added.ciphertext, envelope.ciphertext);
+        assert_eq!(open(&added, carol_identity.clone()).unwrap().plaintext.expose_secret(), "the safe code is 1234");
+        assert!(service.envelope_add_recipients(EnvelopeAddRecipientsRequest {
+            envelope: added.clone(),
+            identity: alice,
+            recipients: vec![carol_recipient],
+        }).is_err());
+
+        let removed = service.envelope_remove_recipients(EnvelopeRemoveRecipientsRequest {
+            envelope: added,
+            ids: vec!["bob".to_string()],
+        }).unwrap();
+        assert_eq!(removed.ciphertext, envelope.ciphertext);
+        assert!(matches!(open(&removed, bob_identity), Err(CryptoError::InvalidInput(_))));
+        assert!(open(&removed, carol_identity).is_ok());
+
+        for ids in [vec!["dave".to_string()], vec!["alice".to_string(), "carol".to_string()]] {
+            let result = service.envelope_remove_recipients(EnvelopeRemoveRecipientsRequest {
+                envelope: removed.clone(),
+                ids,
+            });
+            assert!(matches!(result, Err(CryptoError::InvalidInput(_))));
+        }
+    }
+
+    #[test]
+    fn test_stored_alive_checks_trigger_once() {
+        let service = CryptoBoundaryService::new();
+        let now = Utc::now();
+        let check = AliveCheck {
+            id: "check-1".to_string(),
+            user_id: "user-1".to_string(),
+            interval_days: 30,
+            grace_period_days: 7,
+            last_check_at: None,
+            next_check_due: now + chrono::Duration::days(30),
+            consecutive_missed: 2,
+            max_missed_before_trigger: 3,
+            is_active: true,
+            is_overdue: false,
+        };
+        let evaluated = service.evaluate_alive_checks(EvaluateAliveChecksRequest { checks: vec![check.clone()] }).unwrap();
+        assert!(evaluated.events.is_empty());
+
+        // The API goes quiet; the scheduler's clock runs past the grace period.
+        let clock = deadman::ManualClock::new(now + chrono::Duration::days(40));
+        let events = service.evaluate_stored_alive_checks(&clock).unwrap();
+        assert!(matches!(events.last(), Some(AliveCheckEvent::Triggered { .. })));
+        assert!(service.evaluate_stored_alive_checks(&clock).unwrap().is_empty());
+
+        // The API's stale row triggers again but is not logged twice.
+        let mut stale = check;
+        stale.next_check_due = now - chrono::Duration::days(40);
+        let evaluated = service.evaluate_alive_checks(EvaluateAliveChecksRequest { checks: vec![stale] }).unwrap();
+        assert!(matches!(evaluated.events.last(), Some(AliveCheckEvent::Triggered { .. })));
+        assert_eq!(service.audit_log().unwrap().records.len(), 1);
+    }
+
+    #[test]
+    fn test_sign_verify_with_public_key() {
+        let service = CryptoBoundaryService::new();
+        let jwk = service.generate_signing_key().unwrap();
+        let signed = service.sign(SignRequest {
+            context: "export-archive".to_string(),
+            message: "archive digest".to_string(),
+            kid: None,
+        }).unwrap();
+        assert_eq!(signed.kid, jwk.kid);
+
+        let public_key = general_purpose::STANDARD.encode(general_purpose::URL_SAFE_NO_PAD.decode(&jwk.x).unwrap());
+        let verify = |kid: Option<String>, message: &str| service.verify(VerifyRequest {
+            context: "export-archive".to_string(),
+            message: message.to_string(),
+            signature: signed.signature.clone(),
+            kid,
+            public_key: Some(public_key.clone()),
+        });
+        assert!(verify(None, "archive digest").unwrap().valid);
+        assert!(verify(Some(jwk.kid.clone()), "archive digest").unwrap().valid);
+        assert!(!verify(None, "another digest").unwrap().valid);
+        assert!(verify(Some("someone-else".to_string()), "archive digest").is_err());
+    }
+
+    #[test]
+    fn test_short_salt_is_rejected() {
+        let service = CryptoBoundaryService::new();
+        
+        for salt in ["", "AA==", "AAAAAAAAAA=="] {
+            let result = service.kdf_argon2id(KdfRequest {
+                password: "password".into(),
+                salt: Some(salt.to_string()),
+                memory: Some(64),
+                iterations: Some(1),
+                parallelism: Some(1),
+            });
+            assert!(matches!(result, Err(CryptoError::InvalidInput(_))), "salt {:?}", salt);
+        }
+    }
+}
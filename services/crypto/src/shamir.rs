@@ -0,0 +1,178 @@
+//! Shamir secret sharing over GF(2^8).
+//!
+//! Every byte of the secret is shared with its own random polynomial of degree
+//! `threshold - 1`. The field uses the reduction polynomial 0x11d, the same one
+//! as the web client's `shamir.ts`.
+
+use rand::{RngCore, rngs::OsRng};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::CryptoError;
+
+const fn build_tables() -> ([u8; 256], [u8; 256]) {
+    let mut exp = [0u8; 256];
+    let mut log = [0u8; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= 0x11d;
+        }
+        i += 1;
+    }
+    exp[255] = exp[0];
+    (exp, log)
+}
+
+const TABLES: ([u8; 256], [u8; 256]) = build_tables();
+const EXP: [u8; 256] = TABLES.0;
+const LOG: [u8; 256] = TABLES.1;
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    EXP[(LOG[a as usize] as usize + LOG[b as usize] as usize) % 255]
+}
+
+fn gf_div(a: u8, b: u8) -> u8 {
+    debug_assert!(b != 0, "division by zero in GF(2^8)");
+    if a == 0 {
+        return 0;
+    }
+    EXP[(LOG[a as usize] as usize + 255 - LOG[b as usize] as usize) % 255]
+}
+
+/// A single share: the evaluation point `x` and one `y` byte per secret byte.
+///
+/// Wiped on drop; `Debug` shows only the evaluation point.
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct Share {
+    pub x: u8,
+    pub y: Vec<u8>,
+}
+
+impl std::fmt::Debug for Share {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Share").field("x", &self.x).field("y", &"[REDACTED]").finish()
+    }
+}
+
+impl Share {
+    /// Serialises the share as `x || y`.
+    pub fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+        let mut out = Zeroizing::new(Vec::with_capacity(1 + self.y.len()));
+        out.push(self.x);
+        out.extend_from_slice(&self.y);
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        match bytes.split_first() {
+            Some((&x, y)) if x != 0 && !y.is_empty() => Ok(Self { x, y: y.to_vec() }),
+            _ => Err(CryptoError::InvalidInput("Malformed share".to_string())),
+        }
+    }
+}
+
+/// Splits `secret` into `shares` shares, any `threshold` of which recover it.
+pub fn split(secret: &[u8], threshold: u8, shares: u8) -> Result<Vec<Share>, CryptoError> {
+    if secret.is_empty() {
+        return Err(CryptoError::InvalidInput("Secret must not be empty".to_string()));
+    }
+    if threshold == 0 || threshold > shares {
+        return Err(CryptoError::InvalidInput(
+            "Threshold must be between 1 and the number of shares".to_string()
+        ));
+    }
+
+    let mut out: Vec<Share> = (1..=shares)
+        .map(|x| Share { x, y: Vec::with_capacity(secret.len()) })
+        .collect();
+
+    let mut coefficients = Zeroizing::new(vec![0u8; threshold as usize]);
+    for &byte in secret {
+        coefficients[0] = byte;
+        OsRng.fill_bytes(&mut coefficients[1..]);
+
+        for share in out.iter_mut() {
+            // Horner evaluation of the polynomial at share.x
+            let y = coefficients
+                .iter()
+                .rev()
+                .fold(0u8, |acc, &c| gf_mul(acc, share.x) ^ c);
+            share.y.push(y);
+        }
+    }
+
+    Ok(out)
+}
+
+/// Recovers the secret by Lagrange interpolation at zero.
+///
+/// Supplying fewer shares than the original threshold yields an unrelated
+/// value rather than an error; callers that know the threshold must check it.
+pub fn combine(shares: &[Share]) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+    let first = shares
+        .first()
+        .ok_or_else(|| CryptoError::InvalidInput("At least one share is required".to_string()))?;
+    let len = first.y.len();
+
+    for (i, share) in shares.iter().enumerate() {
+        if share.x == 0 || share.y.len() != len {
+            return Err(CryptoError::InvalidInput("Malformed share".to_string()));
+        }
+        if shares[..i].iter().any(|other| other.x == share.x) {
+            return Err(CryptoError::InvalidInput("Duplicate share".to_string()));
+        }
+    }
+
+    let weights: Vec<u8> = shares
+        .iter()
+        .map(|si| {
+            let (num, den) = shares
+                .iter()
+                .filter(|sj| sj.x != si.x)
+                .fold((1u8, 1u8), |(num, den), sj| {
+                    (gf_mul(num, sj.x), gf_mul(den, si.x ^ sj.x))
+                });
+            gf_div(num, den)
+        })
+        .collect();
+
+    Ok(Zeroizing::new((0..len)
+        .map(|i| {
+            shares
+                .iter()
+                .zip(&weights)
+                .fold(0u8, |acc, (share, &w)| acc ^ gf_mul(share.y[i], w))
+        })
+        .collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_combine_any_subset() {
+        let secret = b"thirty-two bytes of release key!";
+        let shares = split(secret, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        assert_eq!(*combine(&shares[..3]).unwrap(), secret);
+        assert_eq!(*combine(&[shares[4].clone(), shares[0].clone(), shares[2].clone()]).unwrap(), secret);
+        assert_eq!(*combine(&shares).unwrap(), secret);
+        assert_ne!(*combine(&shares[..2]).unwrap(), secret);
+    }
+
+    #[test]
+    fn test_combine_rejects_duplicates() {
+        let shares = split(b"secret", 2, 3).unwrap();
+        let result = combine(&[shares[1].clone(), shares[1].clone()]);
+        assert!(matches!(result, Err(CryptoError::InvalidInput(_))));
+    }
+}
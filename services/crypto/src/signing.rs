@@ -0,0 +1,290 @@
+//! Ed25519 signatures with key ids and domain-separated contexts.
+//!
+//! Signing keys are generated (or imported) inside the boundary: their seeds
+//! live only in the [`KeyStore`], and only the public halves leave it, as a
+//! JWK Set for verifiers. A key's id is its RFC 7638 JWK thumbprint, so
+//! anyone holding the public key can recompute it. Keys are rotated by
+//! restarting with a new seed; the old public keys are loaded back as
+//! retired keys, which stay in the JWK Set but never sign.
+//!
+//! Signatures are plain Ed25519 over
+//! `"last-words signature v1" || 0x00 || len(context) || context || message`,
+//! so a signature made in one context never verifies in another, and any
+//! Ed25519 library can check one given the context. Contexts under
+//! [`SERVICE_CONTEXT_PREFIX`] belong to statements the service makes itself;
+//! `POST /sign` refuses them, so callers cannot forge one.
+
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use base64::{Engine as _, engine::general_purpose};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::{RngCore, rngs::OsRng};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::secure::KeyStore;
+use crate::{CryptoError, Jwk};
+
+pub use ed25519_dalek::{PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH, SIGNATURE_LENGTH};
+
+const LABEL: &[u8] = b"last-words signature v1";
+pub const MAX_CONTEXT_BYTES: usize = 64;
+
+/// Reserved for the service's own statements, such as release certificates.
+pub const SERVICE_CONTEXT_PREFIX: &str = "service/";
+
+/// Key store ids of signing seeds are this prefix and the key id.
+const STORE_PREFIX: &str = "signing/";
+
+/// Contexts are short lowercase names such as `release-certificate`.
+fn check_context(context: &str) -> Result<(), CryptoError> {
+    let valid = (1..=MAX_CONTEXT_BYTES).contains(&context.len())
+        && context.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b"-._/".contains(&b));
+    if !valid {
+        return Err(CryptoError::InvalidInput(format!(
+            "Signature context must be 1 to {} characters of a-z, 0-9 and -._/",
+            MAX_CONTEXT_BYTES
+        )));
+    }
+    Ok(())
+}
+
+/// [`check_context`] for a context chosen by a caller rather than the service.
+pub fn check_caller_context(context: &str) -> Result<(), CryptoError> {
+    check_context(context)?;
+    if context.starts_with(SERVICE_CONTEXT_PREFIX) {
+        return Err(CryptoError::InvalidInput(format!(
+            "Signature context {:?} is reserved for the service",
+            context
+        )));
+    }
+    Ok(())
+}
+
+/// The bytes actually signed for `message` in `context`.
+pub fn signed_message(context: &str, message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    check_context(context)?;
+    Ok([LABEL, &[0, context.len() as u8], context.as_bytes(), message].concat())
+}
+
+/// The RFC 7638 JWK thumbprint of an Ed25519 public key.
+pub fn key_id(public_key: &VerifyingKey) -> String {
+    let x = general_purpose::URL_SAFE_NO_PAD.encode(public_key.as_bytes());
+    let thumbprint = Sha256::digest(format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, x));
+    general_purpose::URL_SAFE_NO_PAD.encode(thumbprint)
+}
+
+/// The RFC 8037 JWK of an Ed25519 public key.
+pub fn jwk(public_key: &VerifyingKey) -> Jwk {
+    Jwk {
+        kty: "OKP".to_string(),
+        crv: "Ed25519".to_string(),
+        alg: "EdDSA".to_string(),
+        usage: "sig".to_string(),
+        kid: key_id(public_key),
+        x: general_purpose::URL_SAFE_NO_PAD.encode(public_key.as_bytes()),
+    }
+}
+
+/// Decodes a base64 Ed25519 public key, rejecting invalid points.
+pub fn decode_public_key(encoded: &str) -> Result<VerifyingKey, CryptoError> {
+    let bytes: [u8; PUBLIC_KEY_LENGTH] = general_purpose::STANDARD
+        .decode(encoded)?
+        .try_into()
+        .map_err(|_| CryptoError::InvalidInput(format!("Public key must be {} bytes", PUBLIC_KEY_LENGTH)))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| CryptoError::InvalidInput("Invalid Ed25519 public key".to_string()))
+}
+
+pub fn decode_signature(encoded: &str) -> Result<Signature, CryptoError> {
+    let bytes = general_purpose::STANDARD.decode(encoded)?;
+    Signature::from_slice(&bytes)
+        .map_err(|_| CryptoError::InvalidInput(format!("Signature must be {} bytes", SIGNATURE_LENGTH)))
+}
+
+/// Verifies strictly: small-order keys and non-canonical signatures fail.
+pub fn verify(
+    public_key: &VerifyingKey,
+    context: &str,
+    message: &[u8],
+    signature: &Signature,
+) -> Result<bool, CryptoError> {
+    Ok(public_key.verify_strict(&signed_message(context, message)?, signature).is_ok())
+}
+
+/// Decodes an Ed25519 JWK, checking that its `kid` is its thumbprint.
+pub fn from_jwk(jwk: &Jwk) -> Result<VerifyingKey, CryptoError> {
+    if jwk.kty != "OKP" || jwk.crv != "Ed25519" {
+        return Err(CryptoError::InvalidInput(format!("Signing key {:?} is not an Ed25519 JWK", jwk.kid)));
+    }
+    let bytes: [u8; PUBLIC_KEY_LENGTH] = general_purpose::URL_SAFE_NO_PAD
+        .decode(&jwk.x)?
+        .try_into()
+        .map_err(|_| CryptoError::InvalidInput(format!("Public key must be {} bytes", PUBLIC_KEY_LENGTH)))?;
+    let public_key = VerifyingKey::from_bytes(&bytes)
+        .map_err(|_| CryptoError::InvalidInput("Invalid Ed25519 public key".to_string()))?;
+    if key_id(&public_key) != jwk.kid {
+        return Err(CryptoError::InvalidInput(format!("Signing key {:?} does not match its kid", jwk.kid)));
+    }
+    Ok(public_key)
+}
+
+#[derive(Debug, Default)]
+struct Keys {
+    /// Oldest first: retired keys, then those with a seed in the store.
+    public: Vec<(String, VerifyingKey)>,
+    active: Option<String>,
+}
+
+/// The public halves of the service's signing keys. One is active and signs
+/// requests that name no key; retired keys, whose seeds are gone, are kept
+/// so signatures made before a rotation still verify.
+#[derive(Debug, Default)]
+pub struct SigningKeys {
+    keys: RwLock<Keys>,
+}
+
+impl SigningKeys {
+    fn read(&self) -> Result<RwLockReadGuard<'_, Keys>, CryptoError> {
+        self.keys.read().map_err(|_| CryptoError::SecureMemory("Signing key lock poisoned".to_string()))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, Keys>, CryptoError> {
+        self.keys.write().map_err(|_| CryptoError::SecureMemory("Signing key lock poisoned".to_string()))
+    }
+
+    /// Publishes a retired key for verification only. Retired keys never sign.
+    pub fn retire(&self, public_key: VerifyingKey) -> Result<(), CryptoError> {
+        let kid = key_id(&public_key);
+        let mut keys = self.write()?;
+        if !keys.public.iter().any(|(id, _)| *id == kid) {
+            keys.public.insert(0, (kid, public_key));
+        }
+        Ok(())
+    }
+
+    /// Stores `seed` in `store` and makes its key the active one.
+    pub fn import(&self, store: &KeyStore, seed: &[u8]) -> Result<VerifyingKey, CryptoError> {
+        let seed: &[u8; SECRET_KEY_LENGTH] = seed.try_into().map_err(|_| {
+            CryptoError::InvalidInput(format!("Signing key seed must be {} bytes", SECRET_KEY_LENGTH))
+        })?;
+        let public_key = SigningKey::from_bytes(seed).verifying_key();
+        let kid = key_id(&public_key);
+        store.insert(&format!("{}{}", STORE_PREFIX, kid), seed)?;
+
+        let mut keys = self.write()?;
+        keys.public.retain(|(id, _)| *id != kid);
+        keys.public.push((kid.clone(), public_key));
+        keys.active = Some(kid);
+        Ok(public_key)
+    }
+
+    /// Generates a key inside the boundary and makes it the active one.
+    pub fn generate(&self, store: &KeyStore) -> Result<VerifyingKey, CryptoError> {
+        let mut seed = Zeroizing::new([0u8; SECRET_KEY_LENGTH]);
+        OsRng.fill_bytes(seed.as_mut());
+        self.import(store, seed.as_ref())
+    }
+
+    pub fn get(&self, kid: &str) -> Result<Option<VerifyingKey>, CryptoError> {
+        Ok(self.read()?.public.iter().find(|(id, _)| id == kid).map(|(_, key)| *key))
+    }
+
+    pub fn public_keys(&self) -> Result<Vec<VerifyingKey>, CryptoError> {
+        Ok(self.read()?.public.iter().map(|(_, key)| *key).collect())
+    }
+
+    /// `kid` itself, or the active key's id if `None`.
+    pub fn resolve(&self, kid: Option<&str>) -> Result<String, CryptoError> {
+        match kid {
+            Some(kid) => Ok(kid.to_string()),
+            None => self
+                .read()?
+                .active
+                .clone()
+                .ok_or_else(|| CryptoError::InvalidInput("No signing key has been generated".to_string())),
+        }
+    }
+
+    /// Signs with `kid`, or with the active key; returns the key id used.
+    pub fn sign(
+        &self,
+        store: &KeyStore,
+        kid: Option<&str>,
+        context: &str,
+        message: &[u8],
+    ) -> Result<(String, Signature), CryptoError> {
+        let kid = self.resolve(kid)?;
+        let message = signed_message(context, message)?;
+        let signature = store
+            .with_key(&format!("{}{}", STORE_PREFIX, kid), |seed| {
+                let seed: &[u8; SECRET_KEY_LENGTH] = seed.try_into().expect("signing seeds are stored whole");
+                SigningKey::from_bytes(seed).sign(&message)
+            })?
+            .ok_or_else(|| CryptoError::InvalidInput(format!("Unknown or retired signing key {:?}", kid)))?;
+        Ok((kid, signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contexts_are_separated() {
+        crate::aead::init().unwrap();
+        let store = KeyStore::default();
+        let keys = SigningKeys::default();
+        let public_key = keys.generate(&store).unwrap();
+
+        let (kid, signature) = keys.sign(&store, None, "release-certificate", b"will-42").unwrap();
+        assert_eq!(kid, key_id(&public_key));
+        assert!(verify(&public_key, "release-certificate", b"will-42", &signature).unwrap());
+        assert!(!verify(&public_key, "audit-checkpoint", b"will-42", &signature).unwrap());
+        assert!(!verify(&public_key, "release-certificate", b"will-43", &signature).unwrap());
+
+        // The length prefix keeps context and message from sliding into each other.
+        assert_ne!(signed_message("ab", b"c").unwrap(), signed_message("a", b"bc").unwrap());
+        for context in ["", "Release", "a b", &"x".repeat(MAX_CONTEXT_BYTES + 1)] {
+            assert!(keys.sign(&store, None, context, b"").is_err(), "{:?}", context);
+        }
+        assert!(check_caller_context("release-event").is_ok());
+        assert!(check_caller_context("service/release-certificate").is_err());
+    }
+
+    #[test]
+    fn test_rotation_keeps_old_keys() {
+        crate::aead::init().unwrap();
+        let store = KeyStore::default();
+        let keys = SigningKeys::default();
+        assert!(keys.sign(&store, None, "test", b"").is_err());
+
+        let old = keys.generate(&store).unwrap();
+        let new = keys.generate(&store).unwrap();
+        assert_eq!(keys.public_keys().unwrap(), vec![old, new]);
+        assert_eq!(keys.sign(&store, None, "test", b"").unwrap().0, key_id(&new));
+
+        let (kid, signature) = keys.sign(&store, Some(&key_id(&old)), "test", b"").unwrap();
+        assert!(verify(&keys.get(&kid).unwrap().unwrap(), "test", b"", &signature).unwrap());
+        assert!(keys.sign(&store, Some("unknown"), "test", b"").is_err());
+    }
+
+    #[test]
+    fn test_retired_keys_verify_but_never_sign() {
+        crate::aead::init().unwrap();
+        let store = KeyStore::default();
+        let keys = SigningKeys::default();
+        let retired = SigningKey::from_bytes(&[0x46; SECRET_KEY_LENGTH]).verifying_key();
+        keys.retire(from_jwk(&jwk(&retired)).unwrap()).unwrap();
+        assert!(keys.sign(&store, None, "test", b"").is_err());
+
+        let active = keys.generate(&store).unwrap();
+        keys.retire(retired).unwrap();
+        assert_eq!(keys.public_keys().unwrap(), vec![retired, active]);
+        assert_eq!(keys.resolve(None).unwrap(), key_id(&active));
+        assert!(keys.sign(&store, Some(&key_id(&retired)), "test", b"").is_err());
+
+        let mut forged = jwk(&active);
+        forged.kid = key_id(&retired);
+        assert!(from_jwk(&forged).is_err());
+    }
+}
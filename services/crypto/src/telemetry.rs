@@ -0,0 +1,172 @@
+//! Structured logs and distributed tracing for the server.
+//!
+//! Logs are JSON lines on stdout (`LOG_FORMAT=pretty` for humans), filtered by
+//! `RUST_LOG` (default `info`). Every HTTP request runs in a `request` span that
+//! carries the caller's `x-request-id` and W3C `traceparent`, so entries line up
+//! with the NestJS API's `LoggingInterceptor`. With the `otlp` feature, setting
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` (e.g. `http://localhost:4318`) also exports the
+//! spans to an OpenTelemetry collector, parented to the caller's trace.
+
+use rand::{RngCore, rngs::OsRng};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+const DEFAULT_FILTER: &str = "info,warp=warn";
+
+/// Keeps the exporter alive; dropping it flushes pending spans.
+pub struct Telemetry {
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                tracing::error!(error = %e, "failed to flush OTLP spans");
+            }
+        }
+    }
+}
+
+/// Installs the global subscriber. Call once, before anything logs.
+pub fn init() -> Telemetry {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let fmt = match std::env::var("LOG_FORMAT").as_deref() {
+        Ok("pretty") => tracing_subscriber::fmt::layer().boxed(),
+        _ => tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed(),
+    };
+
+    #[cfg(feature = "otlp")]
+    {
+        let provider = otlp::provider();
+        let otel = provider.as_ref().and_then(|built| built.as_ref().ok()).map(|provider| {
+            use opentelemetry::trace::TracerProvider as _;
+            tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+        });
+        tracing_subscriber::registry().with(filter).with(fmt).with(otel).init();
+        // Reported only now, so the warning goes out as a JSON log line.
+        let provider = provider.and_then(|built| {
+            built.map_err(|e| tracing::warn!(error = %e, "OTLP exporter disabled")).ok()
+        });
+        Telemetry { provider }
+    }
+
+    #[cfg(not(feature = "otlp"))]
+    {
+        tracing_subscriber::registry().with(filter).with(fmt).init();
+        Telemetry {}
+    }
+}
+
+/// The trace context of an incoming request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceParent {
+    pub trace_id: String,
+    pub parent_id: String,
+    pub sampled: bool,
+}
+
+/// Parses a W3C `traceparent` header (`00-<trace id>-<parent id>-<flags>`).
+pub fn parse_traceparent(header: &str) -> Option<TraceParent> {
+    let mut parts = header.trim().split('-');
+    let (version, trace_id, parent_id, flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    // Version 00 has exactly four fields; later versions may append more.
+    if version.len() != 2 || version == "ff" || (version == "00" && parts.next().is_some()) {
+        return None;
+    }
+
+    let is_hex = |s: &str, len: usize| {
+        s.len() == len && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    };
+    if !is_hex(version, 2) || !is_hex(flags, 2) || !is_hex(trace_id, 32) || !is_hex(parent_id, 16) {
+        return None;
+    }
+    if trace_id.bytes().all(|b| b == b'0') || parent_id.bytes().all(|b| b == b'0') {
+        return None;
+    }
+
+    Some(TraceParent {
+        trace_id: trace_id.to_string(),
+        parent_id: parent_id.to_string(),
+        sampled: u8::from_str_radix(flags, 16).ok()? & 1 == 1,
+    })
+}
+
+/// A fresh id for requests that arrive without `x-request-id`.
+pub fn new_request_id() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Parents `span` to the caller's trace in the OTLP exporter.
+#[cfg(feature = "otlp")]
+pub fn set_remote_parent(span: &tracing::Span, headers: &warp::http::HeaderMap) {
+    otlp::set_remote_parent(span, headers)
+}
+
+#[cfg(not(feature = "otlp"))]
+pub fn set_remote_parent(_span: &tracing::Span, _headers: &warp::http::HeaderMap) {}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use opentelemetry::propagation::{Extractor, TextMapPropagator};
+    use opentelemetry_otlp::ExporterBuildError;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    /// `None` unless `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+    pub fn provider() -> Option<Result<SdkTracerProvider, ExporterBuildError>> {
+        std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT")?;
+        // The builder reads the endpoint and headers from the OTEL_* variables.
+        let exporter = opentelemetry_otlp::SpanExporter::builder().with_http().build();
+        Some(exporter.map(|exporter| SdkTracerProvider::builder().with_batch_exporter(exporter).build()))
+    }
+
+    // warp 0.3 is on http 0.2, so opentelemetry-http's extractor does not apply.
+    struct Headers<'a>(&'a warp::http::HeaderMap);
+
+    impl Extractor for Headers<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|value| value.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(|key| key.as_str()).collect()
+        }
+    }
+
+    pub fn set_remote_parent(span: &tracing::Span, headers: &warp::http::HeaderMap) {
+        let context = TraceContextPropagator::new().extract(&Headers(headers));
+        // Fails only when no OTLP layer is installed, which is fine.
+        let _ = span.set_parent(context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_traceparent() {
+        let parsed = parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        assert_eq!(parsed.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(parsed.parent_id, "00f067aa0ba902b7");
+        assert!(parsed.sampled);
+
+        assert!(parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-extra").is_none());
+        assert!(parse_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none());
+        assert!(parse_traceparent("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01").is_none());
+        assert!(parse_traceparent("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none());
+        assert!(parse_traceparent("garbage").is_none());
+    }
+}
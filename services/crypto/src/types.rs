@@ -0,0 +1,692 @@
+use argon2::PasswordHash;
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::CryptoError;
+use crate::archive::ReleaseArchive;
+use crate::redact::Secret;
+
+// Secret fields are `Secret`: wiped on drop, redacted in `Debug`/`Display` and
+// in `redact::for_log`, and written in full only on the wire.
+// Versioned structs for API responses
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KdfRequest {
+    pub password: Secret,
+    pub salt: Option<String>,
+    pub memory: Option<u32>,
+    pub iterations: Option<u32>,
+    pub parallelism: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KdfResponse {
+    pub version: u8,
+    pub hash: Secret,
+    pub salt: String,
+    pub memory: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl KdfResponse {
+    /// Extracts the raw derived key from the PHC-formatted `hash`.
+    pub fn key(&self) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        let hash = PasswordHash::new(self.hash.expose_secret())
+            .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+        let output = hash
+            .hash
+            .ok_or_else(|| CryptoError::KeyDerivationFailed("Missing hash output".to_string()))?;
+        Ok(Zeroizing::new(output.as_bytes().to_vec()))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AeadEncryptRequest {
+    pub plaintext: Secret,
+    pub key: Secret,
+    pub additional_data: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AeadEncryptResponse {
+    pub version: u8,
+    pub ciphertext: String,
+    pub nonce: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AeadDecryptRequest {
+    pub ciphertext: String,
+    pub key: Secret,
+    pub nonce: String,
+    pub additional_data: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AeadDecryptResponse {
+    pub version: u8,
+    pub plaintext: Secret,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyWrapRequest {
+    pub master_key: Secret,
+    pub user_key: Secret,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyWrapResponse {
+    pub version: u8,
+    pub wrapped_key: String,
+    pub salt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyUnwrapRequest {
+    pub master_key: Secret,
+    pub wrapped_key: String,
+    pub salt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyUnwrapResponse {
+    pub version: u8,
+    pub unwrapped_key: Secret,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShamirSplitRequest {
+    pub secret: Secret,
+    pub threshold: u8,
+    pub shares: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShamirSplitResponse {
+    pub version: u8,
+    pub threshold: u8,
+    pub shares: Vec<Secret>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShamirCombineRequest {
+    pub shares: Vec<Secret>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShamirCombineResponse {
+    pub version: u8,
+    pub secret: Secret,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoxKeypairResponse {
+    pub version: u8,
+    pub public_key: String,
+    pub secret_key: Secret,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoxSealRequest {
+    pub plaintext: Secret,
+    pub public_key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoxSealResponse {
+    pub version: u8,
+    pub ciphertext: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoxOpenRequest {
+    pub ciphertext: String,
+    pub secret_key: Secret,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoxOpenResponse {
+    pub version: u8,
+    pub plaintext: Secret,
+}
+
+/// HPKE base mode, or auth mode when `sender_secret_key` is given.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HpkeSealRequest {
+    pub plaintext: Secret,
+    pub public_key: String,
+    pub info: Option<String>,
+    pub additional_data: Option<String>,
+    pub sender_secret_key: Option<Secret>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HpkeSealResponse {
+    pub version: u8,
+    pub mode: String,
+    pub enc: String,
+    pub ciphertext: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HpkeOpenRequest {
+    pub enc: String,
+    pub ciphertext: String,
+    pub secret_key: Secret,
+    pub info: Option<String>,
+    pub additional_data: Option<String>,
+    pub sender_public_key: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HpkeOpenResponse {
+    pub version: u8,
+    pub plaintext: Secret,
+}
+
+/// A beneficiary to wrap an envelope's content key for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Recipient {
+    /// A 32-byte symmetric key, base64.
+    Symmetric { id: String, key: Secret },
+    /// An X25519 public key, base64; the key is wrapped with HPKE.
+    X25519 { id: String, public_key: String },
+}
+
+/// What a recipient presents to open an envelope.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecipientIdentity {
+    Symmetric { id: String, key: Secret },
+    X25519 { id: String, secret_key: Secret },
+}
+
+/// The content key, wrapped for one recipient.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecipientStanza {
+    Symmetric { id: String, nonce: String, wrapped_key: String },
+    X25519 { id: String, enc: String, wrapped_key: String },
+}
+
+/// A payload encrypted once, with one stanza per recipient.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiRecipientEnvelope {
+    pub version: u8,
+    pub nonce: String,
+    pub ciphertext: String,
+    pub recipients: Vec<RecipientStanza>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvelopeSealRequest {
+    pub plaintext: Secret,
+    pub additional_data: Option<String>,
+    pub recipients: Vec<Recipient>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvelopeOpenRequest {
+    pub envelope: MultiRecipientEnvelope,
+    pub identity: RecipientIdentity,
+    pub additional_data: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvelopeOpenResponse {
+    pub version: u8,
+    pub plaintext: Secret,
+}
+
+/// Adds recipients using an existing recipient's identity to recover the
+/// content key; the payload is not re-encrypted.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvelopeAddRecipientsRequest {
+    pub envelope: MultiRecipientEnvelope,
+    pub identity: RecipientIdentity,
+    pub recipients: Vec<Recipient>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvelopeRemoveRecipientsRequest {
+    pub envelope: MultiRecipientEnvelope,
+    pub ids: Vec<String>,
+}
+
+/// An age recipient: an `age1…` X25519 public key or a passphrase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgeRecipient {
+    X25519 { recipient: String },
+    Scrypt { passphrase: Secret, work_factor: Option<u8> },
+}
+
+/// An age identity: an `AGE-SECRET-KEY-1…` X25519 key or a passphrase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgeIdentity {
+    X25519 { identity: Secret },
+    Scrypt { passphrase: Secret },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgeKeygenResponse {
+    pub version: u8,
+    pub recipient: String,
+    pub identity: Secret,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgeEncryptRequest {
+    pub plaintext: Secret,
+    pub recipients: Vec<AgeRecipient>,
+    /// PEM-armored text (the default) or base64 of the binary file.
+    pub armor: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgeEncryptResponse {
+    pub version: u8,
+    pub file: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgeDecryptRequest {
+    /// An armored age file, or base64 of a binary one.
+    pub file: String,
+    pub identity: AgeIdentity,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgeDecryptResponse {
+    pub version: u8,
+    pub plaintext: Secret,
+}
+
+/// A public Ed25519 signing key as an RFC 8037 JWK.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    pub crv: String,
+    pub alg: String,
+    #[serde(rename = "use")]
+    pub usage: String,
+    pub kid: String,
+    pub x: String,
+}
+
+/// The service's public signing keys as a JWK Set (RFC 7517), oldest first.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SigningKeysResponse {
+    pub keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignRequest {
+    pub context: String,
+    pub message: String,
+    /// Defaults to the active (newest) key.
+    pub kid: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignResponse {
+    pub version: u8,
+    pub kid: String,
+    pub context: String,
+    pub signature: String,
+}
+
+/// Verifies against `public_key` if given, otherwise the service key `kid`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyRequest {
+    pub context: String,
+    pub message: String,
+    pub signature: String,
+    pub kid: Option<String>,
+    pub public_key: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyResponse {
+    pub version: u8,
+    pub valid: bool,
+    pub kid: String,
+}
+
+/// `eventType` of the API's `ReleaseEvent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseEventType {
+    ManualRelease,
+    AutoRelease,
+    AliveCheckFailed,
+    Emergency,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReleaseApproval {
+    pub approved_by: String,
+    pub approved_at: DateTime<Utc>,
+}
+
+/// The signed statement of a release certificate. Unknown fields are
+/// rejected, so what is verified is exactly what was signed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReleaseAttestation {
+    pub version: u8,
+    pub will_id: String,
+    pub release_event_id: String,
+    pub event_type: ReleaseEventType,
+    pub trigger_reason: String,
+    pub approvals: Vec<ReleaseApproval>,
+    pub released_at: DateTime<Utc>,
+    /// `sha256:` and the hex digest of the encrypted will content.
+    pub content_hash: String,
+    pub issued_at: DateTime<Utc>,
+    pub kid: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseCertificate {
+    pub attestation: ReleaseAttestation,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReleaseCertificateRequest {
+    pub will_id: String,
+    pub release_event_id: String,
+    pub event_type: ReleaseEventType,
+    pub trigger_reason: String,
+    #[serde(default)]
+    pub approvals: Vec<ReleaseApproval>,
+    pub released_at: DateTime<Utc>,
+    pub content_hash: String,
+    /// Defaults to the active signing key.
+    pub kid: Option<String>,
+}
+
+/// Verifies against `public_key` if given, otherwise the service key named in
+/// the attestation. With `encrypted_content` (base64), also checks its hash.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyReleaseCertificateRequest {
+    pub certificate: ReleaseCertificate,
+    pub public_key: Option<String>,
+    pub encrypted_content: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyReleaseCertificateResponse {
+    pub version: u8,
+    pub valid: bool,
+    pub kid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_matches: Option<bool>,
+}
+
+/// How the master key of an exported archive is protected: derived from a
+/// passphrase, or generated and split into Shamir shares returned once.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum ArchiveProtection {
+    Passphrase { passphrase: Secret },
+    Shares { threshold: u8, shares: u8 },
+}
+
+/// One file of an exported archive; `contents` is base64.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveItem {
+    pub name: String,
+    pub contents: Secret,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReleaseArchiveRequest {
+    pub will_id: String,
+    pub protection: ArchiveProtection,
+    pub items: Vec<ArchiveItem>,
+}
+
+/// The archive `lastwords-open` reads, and for share protection the shares
+/// of its master key, which are not kept anywhere else.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReleaseArchiveResponse {
+    pub version: u8,
+    pub archive: ReleaseArchive,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shares: Option<Vec<Secret>>,
+}
+
+/// An event for the service's audit log. Absent optional fields are left
+/// out of the record, and so out of its hash.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub operation: String,
+    pub resource: String,
+    pub action: String,
+    pub result: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+/// One link of the audit chain; see [`crate::auditlog`] for how `hash` is taken.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+    /// `None` only for the first record.
+    pub previous_hash: Option<String>,
+    pub hash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditAppendResponse {
+    pub version: u8,
+    pub sequence: u64,
+    /// The appended record's hash, now the head of the chain.
+    pub head: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditLogResponse {
+    pub version: u8,
+    pub records: Vec<AuditRecord>,
+}
+
+/// A chain to check, first record first.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditVerifyRequest {
+    pub records: Vec<AuditRecord>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditChainBreakReason {
+    /// `sequence` is not the record's position in the chain.
+    Sequence,
+    /// `previous_hash` is not the hash of the record before.
+    Link,
+    /// `hash` is not the hash of the record's contents.
+    Hash,
+}
+
+/// The first record at which a chain stops verifying.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditChainBreak {
+    pub index: usize,
+    pub reason: AuditChainBreakReason,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditVerifyResponse {
+    pub version: u8,
+    pub valid: bool,
+    pub length: usize,
+    /// The last record's hash; `None` if the chain is empty or broken.
+    pub head: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_break: Option<AuditChainBreak>,
+}
+
+/// The statement signed in a tree head over the audit log's first
+/// `tree_size` records. Unknown fields are rejected, as for release
+/// certificates.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditTreeHead {
+    pub version: u8,
+    pub tree_size: u64,
+    /// Hex RFC 6962 Merkle tree hash.
+    pub root_hash: String,
+    pub timestamp: DateTime<Utc>,
+    pub kid: String,
+    /// Hash of the log's first record, so heads of a log that started over
+    /// (same sizes, new records) cannot pass for heads of the old one.
+    /// Absent for an empty log.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedTreeHead {
+    pub tree_head: AuditTreeHead,
+    pub signature: String,
+}
+
+/// Defaults to the current tree when `tree_size` is absent.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditInclusionProofRequest {
+    pub sequence: u64,
+    pub tree_size: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditInclusionProofResponse {
+    pub version: u8,
+    pub sequence: u64,
+    pub tree_size: u64,
+    pub leaf_hash: String,
+    /// Hex sibling hashes, leaf first.
+    pub audit_path: Vec<String>,
+}
+
+/// Defaults to the current tree when `second` is absent.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditConsistencyProofRequest {
+    pub first: u64,
+    pub second: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditConsistencyProofResponse {
+    pub version: u8,
+    pub first: u64,
+    pub second: u64,
+    pub proof: Vec<String>,
+}
+
+/// Checks that `record` is in the tree `tree_head` signs, against
+/// `public_key` if given, otherwise the service key it names.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyAuditInclusionRequest {
+    pub record: AuditRecord,
+    pub tree_head: SignedTreeHead,
+    pub audit_path: Vec<String>,
+    pub public_key: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyAuditInclusionResponse {
+    pub version: u8,
+    /// Both the tree head signature and the audit path verified.
+    pub valid: bool,
+    pub kid: String,
+}
+
+/// The scheduling columns of an API `AliveCheck` row, in its field names.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AliveCheck {
+    pub id: String,
+    pub user_id: String,
+    pub interval_days: u32,
+    pub grace_period_days: u32,
+    pub last_check_at: Option<DateTime<Utc>>,
+    pub next_check_due: DateTime<Utc>,
+    pub consecutive_missed: u32,
+    pub max_missed_before_trigger: u32,
+    pub is_active: bool,
+    pub is_overdue: bool,
+}
+
+/// What evaluating an alive check found; see [`crate::deadman`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AliveCheckEvent {
+    /// The check came due unanswered; the grace period has started.
+    Overdue {
+        check_id: String,
+        user_id: String,
+        due_at: DateTime<Utc>,
+        grace_ends_at: DateTime<Utc>,
+    },
+    /// The grace period ended unanswered.
+    Missed {
+        check_id: String,
+        user_id: String,
+        due_at: DateTime<Utc>,
+        consecutive_missed: u32,
+    },
+    /// Too many checks were missed: release the user's wills. The check is
+    /// deactivated, so this is emitted once.
+    Triggered {
+        check_id: String,
+        user_id: String,
+        event_type: ReleaseEventType,
+        trigger_reason: String,
+        triggered_at: DateTime<Utc>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EvaluateAliveChecksRequest {
+    pub checks: Vec<AliveCheck>,
+}
+
+/// The checks as they should now be stored, and what happened to them.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EvaluateAliveChecksResponse {
+    pub version: u8,
+    pub checks: Vec<AliveCheck>,
+    pub events: Vec<AliveCheckEvent>,
+}
+
+/// A check-in from the user behind `check`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfirmAliveCheckRequest {
+    pub check: AliveCheck,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfirmAliveCheckResponse {
+    pub version: u8,
+    /// `false` if the switch had already triggered; a late check-in does not
+    /// undo a release.
+    pub confirmed: bool,
+    pub check: AliveCheck,
+    pub events: Vec<AliveCheckEvent>,
+}
@@ -0,0 +1,159 @@
+//! wasm-bindgen exports for the browser.
+//!
+//! Each export takes and returns plain JS objects shaped exactly like the HTTP
+//! API's JSON bodies, and runs the same `CryptoBoundaryService` code with the
+//! pure-Rust AEAD backend, so browser output is byte-compatible with the server.
+
+use base64::{Engine as _, engine::general_purpose};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{CryptoBoundaryService, CryptoError, KdfResponse};
+
+thread_local! {
+    static SERVICE: CryptoBoundaryService = CryptoBoundaryService::new();
+}
+
+#[derive(Serialize)]
+struct DeriveResponse {
+    #[serde(flatten)]
+    kdf: KdfResponse,
+    key: String,
+}
+
+fn call<Req, Res>(
+    req: JsValue,
+    op: impl FnOnce(&CryptoBoundaryService, Req) -> Result<Res, CryptoError>,
+) -> Result<JsValue, JsError>
+where
+    Req: serde::de::DeserializeOwned,
+    Res: Serialize,
+{
+    let req: Req = serde_wasm_bindgen::from_value(req)?;
+    let res = SERVICE.with(|service| op(service, req))?;
+    Ok(serde_wasm_bindgen::to_value(&res)?)
+}
+
+/// Argon2id derivation; returns the `KdfResponse` plus the raw `key` in base64.
+#[wasm_bindgen]
+pub fn derive(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, |service, req| {
+        let kdf = service.kdf_argon2id(req)?;
+        let key = general_purpose::STANDARD.encode(kdf.key()?);
+        Ok(DeriveResponse { kdf, key })
+    })
+}
+
+#[wasm_bindgen]
+pub fn seal(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::aead_encrypt)
+}
+
+#[wasm_bindgen]
+pub fn open(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::aead_decrypt)
+}
+
+#[wasm_bindgen]
+pub fn wrap(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::key_wrap)
+}
+
+#[wasm_bindgen]
+pub fn unwrap(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::key_unwrap)
+}
+
+#[wasm_bindgen]
+pub fn split(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::shamir_split)
+}
+
+#[wasm_bindgen]
+pub fn combine(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::shamir_combine)
+}
+
+/// Generates a beneficiary's X25519 keypair for sealed boxes.
+#[wasm_bindgen(js_name = boxKeypair)]
+pub fn box_keypair() -> Result<JsValue, JsError> {
+    call(JsValue::NULL, |service, ()| service.box_keypair())
+}
+
+#[wasm_bindgen(js_name = boxSeal)]
+pub fn box_seal(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::box_seal)
+}
+
+#[wasm_bindgen(js_name = boxOpen)]
+pub fn box_open(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::box_open)
+}
+
+#[wasm_bindgen(js_name = hpkeSeal)]
+pub fn hpke_seal(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::hpke_seal)
+}
+
+#[wasm_bindgen(js_name = hpkeOpen)]
+pub fn hpke_open(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::hpke_open)
+}
+
+#[wasm_bindgen(js_name = envelopeSeal)]
+pub fn envelope_seal(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::envelope_seal)
+}
+
+#[wasm_bindgen(js_name = envelopeOpen)]
+pub fn envelope_open(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::envelope_open)
+}
+
+#[wasm_bindgen(js_name = envelopeAddRecipients)]
+pub fn envelope_add_recipients(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::envelope_add_recipients)
+}
+
+#[wasm_bindgen(js_name = envelopeRemoveRecipients)]
+pub fn envelope_remove_recipients(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::envelope_remove_recipients)
+}
+
+/// Generates an X25519 identity for age files.
+#[wasm_bindgen(js_name = ageKeygen)]
+pub fn age_keygen() -> Result<JsValue, JsError> {
+    call(JsValue::NULL, |service, ()| service.age_keygen())
+}
+
+#[wasm_bindgen(js_name = ageEncrypt)]
+pub fn age_encrypt(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::age_encrypt)
+}
+
+#[wasm_bindgen(js_name = ageDecrypt)]
+pub fn age_decrypt(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::age_decrypt)
+}
+
+/// Signing keys live in the server's boundary; the browser only verifies.
+#[wasm_bindgen]
+pub fn verify(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::verify)
+}
+
+#[wasm_bindgen(js_name = verifyReleaseCertificate)]
+pub fn verify_release_certificate(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::verify_release_certificate)
+}
+
+/// Checks an exported audit chain; appending happens only on the server.
+#[wasm_bindgen(js_name = verifyAuditChain)]
+pub fn verify_audit_chain(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::verify_audit_chain)
+}
+
+#[wasm_bindgen(js_name = verifyAuditInclusion)]
+pub fn verify_audit_inclusion(req: JsValue) -> Result<JsValue, JsError> {
+    call(req, CryptoBoundaryService::verify_audit_inclusion)
+}
@@ -0,0 +1,53 @@
+//! Interoperability with the reference `age` implementation (the `age` crate),
+//! in both directions, armored and binary.
+
+use std::str::FromStr;
+
+use age::secrecy::{ExposeSecret, SecretString};
+use last_words_crypto::age::{armor, dearmor, decrypt, encode_recipient, encrypt, generate_identity, parse_recipient};
+use last_words_crypto::{AgeIdentity, AgeRecipient};
+
+/// Long enough to span two STREAM chunks.
+fn plaintext() -> Vec<u8> {
+    (0..70_000u32).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn x25519_files_open_with_age() {
+    let (recipient, identity) = generate_identity();
+    let file = encrypt(&plaintext(), &[AgeRecipient::X25519 { recipient }]).unwrap();
+
+    let identity = age::x25519::Identity::from_str(&identity).unwrap();
+    assert_eq!(age::decrypt(&identity, &file).unwrap(), plaintext());
+    assert_eq!(age::decrypt(&identity, armor(&file).as_bytes()).unwrap(), plaintext());
+}
+
+#[test]
+fn age_x25519_files_open_here() {
+    let identity = age::x25519::Identity::generate();
+    let recipient = identity.to_public();
+    assert_eq!(encode_recipient(&parse_recipient(&recipient.to_string()).unwrap()), recipient.to_string());
+
+    let ours = AgeIdentity::X25519 { identity: identity.to_string().expose_secret().into() };
+    let file = age::encrypt(&recipient, &plaintext()).unwrap();
+    assert_eq!(*decrypt(&file, &ours).unwrap(), plaintext());
+
+    let armored = age::encrypt_and_armor(&recipient, b"").unwrap();
+    assert_eq!(*decrypt(&dearmor(&armored).unwrap(), &ours).unwrap(), b"");
+}
+
+#[test]
+fn scrypt_files_interoperate() {
+    let passphrase = "correct horse battery staple";
+    let recipient = AgeRecipient::Scrypt { passphrase: passphrase.into(), work_factor: Some(10) };
+    let file = encrypt(b"last words", &[recipient]).unwrap();
+    let mut identity = age::scrypt::Identity::new(SecretString::from(passphrase.to_string()));
+    identity.set_max_work_factor(10);
+    assert_eq!(age::decrypt(&identity, &file).unwrap(), b"last words");
+
+    let mut recipient = age::scrypt::Recipient::new(SecretString::from(passphrase.to_string()));
+    recipient.set_work_factor(10);
+    let file = age::encrypt(&recipient, b"last words").unwrap();
+    let ours = AgeIdentity::Scrypt { passphrase: passphrase.into() };
+    assert_eq!(*decrypt(&file, &ours).unwrap(), b"last words");
+}
@@ -0,0 +1,749 @@
+//! The HTTP route tree driven through `warp::test`, without binding a socket.
+
+#![cfg(feature = "server")]
+
+use std::sync::{Arc, Mutex};
+
+use base64::{Engine as _, engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD}};
+use last_words_crypto::http::{self, MAX_BODY_BYTES};
+use last_words_crypto::events::{self, EventSink, OperationEvent};
+use last_words_crypto::{
+    AeadDecryptRequest, AeadEncryptRequest, AgeDecryptRequest, AgeEncryptRequest, AgeIdentity, AgeRecipient,
+    BoxOpenRequest, BoxSealRequest, CryptoBoundaryService, Secret, auditlog, canonical, certificate, health,
+    selftest,
+};
+use last_words_crypto::archive::{self, ArchiveFile};
+use serde_json::{Value, json};
+use warp::Filter;
+use warp::http::{Response, StatusCode};
+use warp::hyper::body::Bytes;
+
+const ADMIN_TOKEN: &str = "test-admin-token-0123456789abcdef";
+
+fn service() -> Arc<CryptoBoundaryService> {
+    Arc::new(CryptoBoundaryService::new())
+}
+
+fn routes(
+    service: Arc<CryptoBoundaryService>,
+) -> impl Filter<Extract = impl warp::Reply, Error = std::convert::Infallible> + Clone + 'static {
+    http::routes(service, Some(ADMIN_TOKEN.into()))
+}
+
+async fn post(
+    routes: &(impl Filter<Extract = impl warp::Reply, Error = std::convert::Infallible> + Clone + 'static),
+    path: &str,
+    body: Value,
+) -> Response<Bytes> {
+    warp::test::request().method("POST").path(path).json(&body).reply(routes).await
+}
+
+async fn post_admin(
+    routes: &(impl Filter<Extract = impl warp::Reply, Error = std::convert::Infallible> + Clone + 'static),
+    path: &str,
+    body: Value,
+) -> Response<Bytes> {
+    warp::test::request()
+        .method("POST")
+        .path(path)
+        .header("authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&body)
+        .reply(routes)
+        .await
+}
+
+async fn get_admin(
+    routes: &(impl Filter<Extract = impl warp::Reply, Error = std::convert::Infallible> + Clone + 'static),
+    path: &str,
+) -> Response<Bytes> {
+    warp::test::request()
+        .path(path)
+        .header("authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .reply(routes)
+        .await
+}
+
+fn json_body(response: &Response<Bytes>) -> Value {
+    serde_json::from_slice(response.body()).unwrap()
+}
+
+fn key() -> String {
+    STANDARD.encode([7u8; 32])
+}
+
+#[tokio::test]
+async fn kdf_argon2id() {
+    let routes = routes(service());
+    let response = post(&routes, "/kdf/argon2id", json!({
+        "password": "correct horse",
+        "salt": STANDARD.encode([1u8; 16]),
+        "memory": 64,
+        "iterations": 1,
+        "parallelism": 1
+    })).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_body(&response);
+    assert_eq!(body["version"], 1);
+    assert_eq!(body["memory"], 64);
+    assert!(body["hash"].as_str().unwrap().starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+}
+
+#[tokio::test]
+async fn aead_encrypt_then_decrypt() {
+    let routes = routes(service());
+    let encrypted = json_body(&post(&routes, "/aead/encrypt", json!({
+        "plaintext": "To my family",
+        "key": key(),
+        "additional_data": "letter.txt"
+    })).await);
+
+    let response = post(&routes, "/aead/decrypt", json!({
+        "ciphertext": encrypted["ciphertext"],
+        "key": key(),
+        "nonce": encrypted["nonce"],
+        "additional_data": "letter.txt"
+    })).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(&response)["plaintext"], "To my family");
+}
+
+#[tokio::test]
+async fn key_wrap_then_unwrap() {
+    let routes = routes(service());
+    let user_key = STANDARD.encode([9u8; 32]);
+    let wrapped = json_body(&post(&routes, "/key/wrap", json!({
+        "master_key": key(),
+        "user_key": user_key
+    })).await);
+
+    let response = post(&routes, "/key/unwrap", json!({
+        "master_key": key(),
+        "wrapped_key": wrapped["wrapped_key"],
+        "salt": wrapped["salt"]
+    })).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(&response)["unwrapped_key"], user_key);
+}
+
+#[tokio::test]
+async fn crypto_errors_are_reported_in_the_body() {
+    let routes = routes(service());
+    let response = post(&routes, "/aead/decrypt", json!({
+        "ciphertext": STANDARD.encode([0u8; 32]),
+        "key": key(),
+        "nonce": STANDARD.encode([0u8; 24])
+    })).await;
+
+    // The API contract: 200 with an `error` field, never the secret inputs.
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_body(&response);
+    assert!(body["error"].as_str().unwrap().starts_with("Decryption failed"));
+    assert!(!String::from_utf8_lossy(response.body()).contains(&key()));
+}
+
+#[tokio::test]
+async fn health_endpoints() {
+    let service = service();
+    let routes = routes(service.clone());
+
+    for path in ["/health", "/health/live"] {
+        let response = warp::test::request().path(path).reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(&response)["version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    health::global().run_self_tests(&service);
+    let response = warp::test::request().path("/health/ready").reply(&routes).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(&response)["status"], "ready");
+}
+
+#[tokio::test]
+async fn metrics_endpoint() {
+    let routes = routes(service());
+    post(&routes, "/aead/encrypt", json!({ "plaintext": "x", "key": "c2hvcnQ=" })).await;
+
+    let response = warp::test::request().path("/metrics").reply(&routes).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+    let text = String::from_utf8_lossy(response.body());
+    assert!(text.contains(r#"crypto_boundary_errors_total{endpoint="aead_encrypt",kind="InvalidInput"}"#));
+}
+
+#[tokio::test]
+async fn cors_preflight() {
+    let routes = routes(service());
+    let response = warp::test::request()
+        .method("OPTIONS")
+        .path("/aead/encrypt")
+        .header("origin", "http://localhost:3000")
+        .header("access-control-request-method", "POST")
+        .header("access-control-request-headers", "content-type, x-request-id")
+        .reply(&routes)
+        .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["access-control-allow-origin"], "http://localhost:3000");
+    let methods = response.headers()["access-control-allow-methods"].to_str().unwrap();
+    assert!(methods.contains("POST"));
+}
+
+#[tokio::test]
+async fn cors_preflight_rejects_unknown_headers() {
+    let routes = routes(service());
+    let response = warp::test::request()
+        .method("OPTIONS")
+        .path("/aead/encrypt")
+        .header("origin", "http://localhost:3000")
+        .header("access-control-request-method", "POST")
+        .header("access-control-request-headers", "x-not-allowed")
+        .reply(&routes)
+        .await;
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn malformed_json_is_400() {
+    let routes = routes(service());
+    for body in [&b"{not json"[..], br#"{"plaintext": "x"}"#, br#"{"plaintext": 1, "key": "a"}"#] {
+        let response = warp::test::request()
+            .method("POST")
+            .path("/aead/encrypt")
+            .header("content-type", "application/json")
+            .body(body)
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", String::from_utf8_lossy(body));
+        assert!(json_body(&response)["error"].as_str().unwrap().starts_with("Malformed JSON body at line 1"));
+    }
+
+    // The reply gives the position only, never serde's text quoting the body.
+    let response = warp::test::request()
+        .method("POST")
+        .path("/kdf/argon2id")
+        .json(&json!({ "password": "x", "memory": "last-words-secret" }))
+        .reply(&routes)
+        .await;
+    let error = json_body(&response)["error"].as_str().unwrap().to_string();
+    assert!(error.starts_with("Malformed JSON body at line 1, column "), "{}", error);
+    assert!(!error.contains("last-words-secret"), "{}", error);
+}
+
+#[tokio::test]
+async fn oversized_body_is_413() {
+    let routes = routes(service());
+    let plaintext = "a".repeat(MAX_BODY_BYTES as usize);
+    let response = post(&routes, "/aead/encrypt", json!({ "plaintext": plaintext, "key": key() })).await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn wrong_method_is_405() {
+    let routes = routes(service());
+    for (method, path) in [("GET", "/kdf/argon2id"), ("PUT", "/aead/encrypt"), ("POST", "/health/ready")] {
+        let response = warp::test::request().method(method).path(path).reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED, "{} {}", method, path);
+    }
+}
+
+#[tokio::test]
+async fn unknown_path_is_404() {
+    let routes = routes(service());
+    let response = warp::test::request().path("/kdf/scrypt").reply(&routes).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(json_body(&response)["error"], "Not found");
+}
+
+#[tokio::test]
+async fn box_keypair_seal_then_open() {
+    let routes = routes(service());
+    let keypair = json_body(&warp::test::request().method("POST").path("/box/keypair").reply(&routes).await);
+    assert_eq!(STANDARD.decode(keypair["public_key"].as_str().unwrap()).unwrap().len(), 32);
+
+    let sealed = json_body(&post(&routes, "/box/seal", json!({
+        "plaintext": "share 1 of 3",
+        "public_key": keypair["public_key"]
+    })).await);
+
+    let response = post(&routes, "/box/open", json!({
+        "ciphertext": sealed["ciphertext"],
+        "secret_key": keypair["secret_key"]
+    })).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(&response)["plaintext"], "share 1 of 3");
+}
+
+#[tokio::test]
+async fn hpke_seal_then_open() {
+    let routes = routes(service());
+    let keypair = json_body(&warp::test::request().method("POST").path("/box/keypair").reply(&routes).await);
+
+    let sealed = json_body(&post(&routes, "/hpke/seal", json!({
+        "plaintext": "share 2 of 3",
+        "public_key": keypair["public_key"],
+        "info": "release"
+    })).await);
+    assert_eq!(sealed["mode"], "base");
+
+    let response = post(&routes, "/hpke/open", json!({
+        "enc": sealed["enc"],
+        "ciphertext": sealed["ciphertext"],
+        "secret_key": keypair["secret_key"],
+        "info": "release"
+    })).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(&response)["plaintext"], "share 2 of 3");
+}
+
+#[tokio::test]
+async fn envelope_seal_add_then_open() {
+    let routes = routes(service());
+    let keypair = json_body(&warp::test::request().method("POST").path("/box/keypair").reply(&routes).await);
+
+    let envelope = json_body(&post(&routes, "/envelope/seal", json!({
+        "plaintext": "for everyone",
+        "recipients": [{ "type": "symmetric", "id": "executor", "key": key() }]
+    })).await);
+    assert_eq!(envelope["version"], 1);
+
+    let envelope = json_body(&post(&routes, "/envelope/recipients/add", json!({
+        "envelope": envelope,
+        "identity": { "type": "symmetric", "id": "executor", "key": key() },
+        "recipients": [{ "type": "x25519", "id": "bob", "public_key": keypair["public_key"] }]
+    })).await);
+    assert_eq!(envelope["recipients"][1]["type"], "x25519");
+
+    let response = post(&routes, "/envelope/open", json!({
+        "envelope": envelope,
+        "identity": { "type": "x25519", "id": "bob", "secret_key": keypair["secret_key"] }
+    })).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(&response)["plaintext"], "for everyone");
+
+    let envelope = json_body(&post(&routes, "/envelope/recipients/remove", json!({
+        "envelope": envelope,
+        "ids": ["executor"]
+    })).await);
+    assert_eq!(envelope["recipients"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn age_keygen_encrypt_then_decrypt() {
+    let routes = routes(service());
+    let keypair = json_body(&warp::test::request().method("POST").path("/age/keygen").reply(&routes).await);
+    assert!(keypair["recipient"].as_str().unwrap().starts_with("age1"));
+
+    let encrypted = json_body(&post(&routes, "/age/encrypt", json!({
+        "plaintext": "export.json",
+        "recipients": [{ "type": "x25519", "recipient": keypair["recipient"] }]
+    })).await);
+    assert!(encrypted["file"].as_str().unwrap().starts_with("-----BEGIN AGE ENCRYPTED FILE-----\n"));
+
+    let response = post(&routes, "/age/decrypt", json!({
+        "file": encrypted["file"],
+        "identity": { "type": "x25519", "identity": keypair["identity"] }
+    })).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(&response)["plaintext"], "export.json");
+}
+
+#[tokio::test]
+async fn sign_then_verify_with_published_key() {
+    let service = service();
+    service.generate_signing_key().unwrap();
+    let routes = routes(service);
+
+    let jwks = json_body(&warp::test::request().path("/keys/signing").reply(&routes).await);
+    let jwk = &jwks["keys"][0];
+    assert_eq!((&jwk["kty"], &jwk["crv"], &jwk["alg"]), (&json!("OKP"), &json!("Ed25519"), &json!("EdDSA")));
+
+    let signed = json_body(&post_admin(&routes, "/sign", json!({
+        "context": "release-event",
+        "message": "will-42 released"
+    })).await);
+    assert_eq!(signed["kid"], jwk["kid"]);
+
+    for (context, valid) in [("release-event", true), ("audit-checkpoint", false)] {
+        let response = post(&routes, "/verify", json!({
+            "context": context,
+            "message": "will-42 released",
+            "signature": signed["signature"],
+            "kid": signed["kid"]
+        })).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(&response)["valid"], valid);
+    }
+}
+
+#[tokio::test]
+async fn admin_routes_need_the_admin_token() {
+    let service = service();
+    service.generate_signing_key().unwrap();
+    let routes = routes(service.clone());
+    let sign = json!({ "context": "release-event", "message": "will-42 released" });
+
+    let response = post(&routes, "/sign", sign.clone()).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = warp::test::request()
+        .method("POST")
+        .path("/audit/append")
+        .header("authorization", "Bearer not-the-admin-token")
+        .json(&json!({ "operation": "forge", "resource": "will", "action": "release", "result": "success" }))
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(service.audit_log().unwrap().records.is_empty());
+    let response = warp::test::request().path("/audit/log").reply(&routes).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // The signing key is never replaced over HTTP.
+    let response = warp::test::request().method("POST").path("/keys/signing").reply(&routes).await;
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+    // Without a configured token, admin routes are closed to everyone.
+    let closed = http::routes(service, None);
+    let response = post(&closed, "/sign", sign).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn sign_refuses_service_contexts() {
+    let service = service();
+    let jwk = service.generate_signing_key().unwrap();
+    let routes = routes(service);
+    let attestation = json!({
+        "version": 1,
+        "will_id": "will-42",
+        "release_event_id": "event-9",
+        "event_type": "emergency",
+        "trigger_reason": "forged",
+        "approvals": [],
+        "released_at": "2026-03-01T10:00:00Z",
+        "content_hash": certificate::content_hash(b"anything"),
+        "issued_at": "2026-03-01T10:00:00Z",
+        "kid": jwk.kid
+    });
+    let message = canonical::to_string(&attestation).unwrap();
+
+    let refused = json_body(&post_admin(&routes, "/sign", json!({
+        "context": certificate::CONTEXT,
+        "message": message
+    })).await);
+    assert!(refused["error"].as_str().unwrap().contains("reserved"), "{}", refused);
+    let refused = json_body(&post_admin(&routes, "/sign", json!({
+        "context": auditlog::TREE_HEAD_CONTEXT,
+        "message": "{}"
+    })).await);
+    assert!(refused["error"].as_str().unwrap().contains("reserved"), "{}", refused);
+
+    // Any other context, the old bare name included, never yields a certificate.
+    let signed = json_body(&post_admin(&routes, "/sign", json!({
+        "context": "release-certificate",
+        "message": message
+    })).await);
+    let forged = json!({ "certificate": { "attestation": attestation, "signature": signed["signature"] } });
+    let body = json_body(&post(&routes, "/release/certificate/verify", forged).await);
+    assert_eq!(body["valid"], false);
+}
+
+#[tokio::test]
+async fn release_certificate_round_trip() {
+    let service = service();
+    service.generate_signing_key().unwrap();
+    let routes = routes(service);
+
+    let content = b"encrypted will bytes";
+    let issued = json_body(&post_admin(&routes, "/release/certificate", json!({
+        "will_id": "will-42",
+        "release_event_id": "event-9",
+        "event_type": "manual_release",
+        "trigger_reason": "executor request",
+        "approvals": [{ "approved_by": "executor-1", "approved_at": "2026-03-01T09:00:00Z" }],
+        "released_at": "2026-03-01T10:00:00Z",
+        "content_hash": certificate::content_hash(content)
+    })).await);
+    assert_eq!(issued["attestation"]["version"], 1);
+
+    let response = post(&routes, "/release/certificate/verify", json!({
+        "certificate": issued,
+        "encrypted_content": STANDARD.encode(content)
+    })).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_body(&response);
+    assert_eq!((&body["valid"], &body["content_matches"]), (&json!(true), &json!(true)));
+
+    let mut forged = issued.clone();
+    forged["attestation"]["event_type"] = json!("emergency");
+    let body = json_body(&post(&routes, "/release/certificate/verify", json!({ "certificate": forged })).await);
+    assert_eq!(body["valid"], false);
+    assert!(body.get("content_matches").is_none());
+}
+
+#[tokio::test]
+async fn exported_release_archive_opens_offline() {
+    let service = service();
+    let routes = routes(service.clone());
+    let request = json!({
+        "will_id": "will-42",
+        "protection": { "method": "shares", "threshold": 2, "shares": 3 },
+        "items": [{ "name": "letter.txt", "contents": STANDARD.encode("To my family") }]
+    });
+    assert_eq!(post(&routes, "/release/archive", request.clone()).await.status(), StatusCode::UNAUTHORIZED);
+
+    let exported = json_body(&post_admin(&routes, "/release/archive", request).await);
+    let shares: Vec<Secret> = serde_json::from_value(exported["shares"].clone()).unwrap();
+    assert_eq!(shares.len(), 3);
+    let ArchiveFile::Archive(archive) = serde_json::from_value(exported["archive"].clone()).unwrap() else {
+        panic!("archive parsed as envelope");
+    };
+
+    // What lastwords-open does with two of the shares.
+    let master_key = archive::shares_key(&service, shares[1..].to_vec(), &archive.unlock).unwrap();
+    let content_key = archive.content_key(&service, &master_key).unwrap();
+    assert_eq!(archive.envelopes[0].name, "letter.txt");
+    assert_eq!(archive.envelopes[0].open(&service, &content_key).unwrap().as_slice(), b"To my family");
+}
+
+#[tokio::test]
+async fn audit_append_then_verify_export() {
+    let routes = routes(service());
+
+    let mut head = Value::Null;
+    for (sequence, action) in ["approve", "release", "access"].into_iter().enumerate() {
+        let appended = json_body(&post_admin(&routes, "/audit/append", json!({
+            "operation": "release",
+            "resource": "will",
+            "action": action,
+            "result": "success",
+            "details": { "will": { "id": "will-42" } }
+        })).await);
+        assert_eq!(appended["sequence"], sequence);
+        head = appended["head"].clone();
+    }
+
+    let export = json_body(&get_admin(&routes, "/audit/log").await);
+    let body = json_body(&post(&routes, "/audit/verify", json!({ "records": export["records"] })).await);
+    assert_eq!((&body["valid"], &body["length"], &body["head"]), (&json!(true), &json!(3), &head));
+
+    let mut records = export["records"].clone();
+    records[1]["details"]["will"]["id"] = json!("will-43");
+    let body = json_body(&post(&routes, "/audit/verify", json!({ "records": records })).await);
+    assert_eq!(body["valid"], false);
+    assert_eq!((&body["first_break"]["index"], &body["first_break"]["reason"]), (&json!(1), &json!("hash")));
+}
+
+#[tokio::test]
+async fn audit_inclusion_proof_against_signed_tree_head() {
+    let service = service();
+    let jwk = service.generate_signing_key().unwrap();
+    let routes = routes(service);
+
+    for action in ["approve", "release", "access"] {
+        post_admin(&routes, "/audit/append", json!({
+            "operation": "release", "resource": "will", "action": action, "result": "success"
+        })).await;
+    }
+    let first = json_body(&post_admin(&routes, "/audit/checkpoint", json!(null)).await);
+    assert_eq!(first["tree_head"]["tree_size"], 3);
+    assert_eq!(json_body(&warp::test::request().path("/audit/tree-head").reply(&routes).await), first);
+
+    // An auditor holding only the tree head, one record and its audit path.
+    let export = json_body(&get_admin(&routes, "/audit/log").await);
+    assert_eq!(first["tree_head"]["log_id"], export["records"][0]["hash"]);
+    let record = &export["records"][1];
+    let proof = json_body(&post(&routes, "/audit/proof/inclusion", json!({ "sequence": 1 })).await);
+    let public_key = STANDARD.encode(URL_SAFE_NO_PAD.decode(&jwk.x).unwrap());
+    let verify = |record: &Value| json!({
+        "record": record,
+        "tree_head": first,
+        "audit_path": proof["audit_path"],
+        "public_key": public_key
+    });
+    let body = json_body(&post(&routes, "/audit/verify/inclusion", verify(record)).await);
+    assert_eq!((&body["valid"], &body["kid"]), (&json!(true), &json!(jwk.kid)));
+
+    let mut forged = record.clone();
+    forged["result"] = json!("failure");
+    assert_eq!(json_body(&post(&routes, "/audit/verify/inclusion", verify(&forged)).await)["valid"], false);
+
+    post_admin(&routes, "/audit/append", json!({
+        "operation": "release", "resource": "will", "action": "close", "result": "success"
+    })).await;
+    let second = json_body(&post_admin(&routes, "/audit/checkpoint", json!(null)).await);
+    assert_eq!(second["tree_head"]["tree_size"], 4);
+    assert_eq!(second["tree_head"]["log_id"], first["tree_head"]["log_id"]);
+    let consistency = json_body(&post(&routes, "/audit/proof/consistency", json!({ "first": 3 })).await);
+    assert_eq!(consistency["second"], 4);
+    assert!(!consistency["proof"].as_array().unwrap().is_empty());
+}
+
+/// Keeps the events of one caller, verified or claimed, so tests sharing the
+/// global sinks do not see each other's.
+struct CallerSink {
+    caller: &'static str,
+    events: Mutex<Vec<Value>>,
+}
+
+impl EventSink for CallerSink {
+    fn emit(&self, event: &OperationEvent) {
+        if [&event.caller, &event.claimed_caller].iter().any(|caller| caller.as_deref() == Some(self.caller)) {
+            self.events.lock().unwrap().push(serde_json::to_value(event).unwrap());
+        }
+    }
+}
+
+#[tokio::test]
+async fn every_unwrap_emits_a_secret_free_audit_event() {
+    let sink = Arc::new(CallerSink { caller: "user-049", events: Mutex::new(Vec::new()) });
+    events::global().add(sink.clone());
+    let routes = routes(service());
+
+    let user_key = STANDARD.encode([9u8; 32]);
+    let wrapped = json_body(&post(&routes, "/key/wrap", json!({
+        "master_key": key(),
+        "user_key": user_key
+    })).await);
+    for salt in [wrapped["salt"].clone(), json!(STANDARD.encode([0u8; 16]))] {
+        warp::test::request()
+            .method("POST")
+            .path("/key/unwrap")
+            .header("x-caller-id", "user-049")
+            .header("x-request-id", "req-1")
+            .json(&json!({ "master_key": key(), "wrapped_key": wrapped["wrapped_key"], "salt": salt }))
+            .reply(&routes)
+            .await;
+    }
+
+    let events = sink.events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!((&events[0]["outcome"], &events[1]["outcome"]), (&json!("success"), &json!("failure")));
+    assert_eq!(events[1]["error"], "DecryptionFailed");
+    for event in events.iter() {
+        assert_eq!((&event["operation"], &event["request_id"]), (&json!("key_unwrap"), &json!("req-1")));
+        assert_eq!((&event["caller"], &event["claimed_caller"]), (&Value::Null, &json!("user-049")));
+        assert_eq!(event["key_id"], events::key_id(&key()).unwrap());
+        let logged = event.to_string();
+        assert!(!logged.contains(&key()) && !logged.contains(&user_key), "secret in {}", logged);
+    }
+}
+
+#[test]
+fn library_calls_emit_one_event_each_with_the_key_id() {
+    let sink = Arc::new(CallerSink { caller: "library", events: Mutex::new(Vec::new()) });
+    events::global().add(sink.clone());
+    let service = CryptoBoundaryService::new();
+    let keypair = service.box_keypair().unwrap();
+    let (recipient, identity) = {
+        let generated = service.age_keygen().unwrap();
+        (generated.recipient, generated.identity)
+    };
+
+    let caller = events::Caller { id: Some("library".to_string()), ..Default::default() };
+    events::as_caller(caller, || {
+        let sealed = service.box_seal(BoxSealRequest {
+            plaintext: "last words".into(),
+            public_key: keypair.public_key.clone(),
+        }).unwrap();
+        service.box_open(BoxOpenRequest { ciphertext: sealed.ciphertext, secret_key: keypair.secret_key.clone() }).unwrap();
+
+        // aead_decrypt runs aead_open; only the outer call is reported.
+        let encrypted = service.aead_encrypt(AeadEncryptRequest {
+            plaintext: "last words".into(),
+            key: key().into(),
+            additional_data: None,
+        }).unwrap();
+        service.aead_decrypt(AeadDecryptRequest {
+            ciphertext: encrypted.ciphertext,
+            key: key().into(),
+            nonce: encrypted.nonce,
+            additional_data: None,
+        }).unwrap();
+
+        let file = service.age_encrypt(AgeEncryptRequest {
+            plaintext: "last words".into(),
+            recipients: vec![AgeRecipient::X25519 { recipient }],
+            armor: None,
+        }).unwrap().file;
+        service.age_decrypt(AgeDecryptRequest { file, identity: AgeIdentity::X25519 { identity } }).unwrap();
+
+        // Self-tests are synthetic and report nothing.
+        assert!(selftest::run(&service).iter().all(|result| result.passed));
+    });
+
+    let events = sink.events.lock().unwrap();
+    let operations: Vec<&Value> = events.iter().map(|event| &event["operation"]).collect();
+    assert_eq!(operations, ["box_seal", "box_open", "aead_encrypt", "aead_decrypt", "age_encrypt", "age_decrypt"]);
+    // Sealing to a key pair and opening with it log the same id.
+    assert_eq!(events[0]["key_id"], events[1]["key_id"]);
+    assert_eq!(events[1]["key_id"], events::key_id(&keypair.public_key).unwrap());
+    assert_eq!(events[3]["key_id"], events::key_id(&key()).unwrap());
+    assert!(events[5]["key_id"].is_string());
+}
+
+#[tokio::test]
+async fn admin_routes_attribute_events_to_the_admin_token() {
+    let sink = Arc::new(CallerSink { caller: "someone-else", events: Mutex::new(Vec::new()) });
+    events::global().add(sink.clone());
+    let service = service();
+    service.generate_signing_key().unwrap();
+    let routes = routes(service);
+
+    warp::test::request()
+        .method("POST")
+        .path("/sign")
+        .header("authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .header("x-caller-id", "someone-else")
+        .json(&json!({ "context": "release-event", "message": "will-42 released" }))
+        .reply(&routes)
+        .await;
+
+    let events = sink.events.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!((&events[0]["caller"], &events[0]["claimed_caller"]), (&json!("admin"), &json!("someone-else")));
+}
+
+#[tokio::test]
+async fn alive_check_trigger_is_recorded_in_the_audit_log() {
+    let routes = routes(service());
+    let check = |next_check_due: &str| json!({
+        "id": "check-1",
+        "userId": "user-1",
+        "intervalDays": 30,
+        "gracePeriodDays": 7,
+        "lastCheckAt": null,
+        "nextCheckDue": next_check_due,
+        "consecutiveMissed": 2,
+        "maxMissedBeforeTrigger": 3,
+        "isActive": true,
+        "isOverdue": true
+    });
+
+    let far_future = "2999-01-01T00:00:00Z";
+    let body = json_body(&post_admin(&routes, "/alive-checks/evaluate", json!({ "checks": [check(far_future)] })).await);
+    assert_eq!(body["events"], json!([]));
+    assert_eq!((&body["checks"][0]["isActive"], &body["checks"][0]["isOverdue"]), (&json!(true), &json!(false)));
+
+    let body = json_body(&post_admin(&routes, "/alive-checks/evaluate", json!({
+        "checks": [check("2020-01-01T00:00:00Z")]
+    })).await);
+    let kinds: Vec<&Value> = body["events"].as_array().unwrap().iter().map(|event| &event["type"]).collect();
+    assert_eq!(kinds, [&json!("missed"), &json!("triggered")]);
+    assert_eq!(body["events"][1]["event_type"], "alive_check_failed");
+    assert_eq!(body["checks"][0]["isActive"], false);
+
+    let confirmed = json_body(&post_admin(&routes, "/alive-checks/confirm", json!({ "check": body["checks"][0] })).await);
+    assert_eq!(confirmed["confirmed"], false);
+
+    // A retried or stale row reports the trigger again but logs it once.
+    let retried = json_body(&post_admin(&routes, "/alive-checks/evaluate", json!({
+        "checks": [check("2020-01-01T00:00:00Z")]
+    })).await);
+    assert_eq!(retried["events"][1]["type"], "triggered");
+
+    let export = json_body(&get_admin(&routes, "/audit/log").await);
+    assert_eq!(export["records"].as_array().unwrap().len(), 1);
+    assert_eq!(export["records"][0]["action"], "trigger");
+    assert_eq!(export["records"][0]["details"]["check_id"], "check-1");
+}
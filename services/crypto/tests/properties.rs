@@ -0,0 +1,130 @@
+//! Round-trip properties over arbitrary inputs.
+//!
+//! Fuzz targets for every request path live in `fuzz/` (run with
+//! `cargo +nightly fuzz run <target>`); these properties run with the normal
+//! test suite.
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use last_words_crypto::{
+    AeadDecryptRequest, AeadEncryptRequest, CryptoBoundaryService, CryptoError, KeyUnwrapRequest,
+    KeyWrapRequest, ShamirCombineRequest, ShamirSplitRequest,
+};
+use proptest::prelude::*;
+use proptest::sample::subsequence;
+use secrecy::ExposeSecret;
+
+fn service() -> &'static CryptoBoundaryService {
+    static SERVICE: std::sync::OnceLock<CryptoBoundaryService> = std::sync::OnceLock::new();
+    SERVICE.get_or_init(CryptoBoundaryService::new)
+}
+
+/// A secret, a `k`-of-`n` split of it, and the indices of `k` shares to
+/// combine, in random order since combining must not depend on it.
+fn split_case() -> impl Strategy<Value = (Vec<u8>, u8, u8, Vec<usize>)> {
+    (prop::collection::vec(any::<u8>(), 1..64), 1u8..=16)
+        .prop_flat_map(|(secret, n)| (Just(secret), 1..=n, Just(n)))
+        .prop_flat_map(|(secret, k, n)| {
+            let indices = subsequence((0..n as usize).collect::<Vec<_>>(), k as usize).prop_shuffle();
+            (Just(secret), Just(k), Just(n), indices)
+        })
+}
+
+proptest! {
+    #[test]
+    fn aead_round_trip(
+        plaintext in ".*",
+        key in prop::array::uniform32(any::<u8>()),
+        additional_data in proptest::option::of(".*"),
+    ) {
+        let key = STANDARD.encode(key);
+        let encrypted = service().aead_encrypt(AeadEncryptRequest {
+            plaintext: plaintext.as_str().into(),
+            key: key.as_str().into(),
+            additional_data: additional_data.clone(),
+        }).unwrap();
+        let decrypted = service().aead_decrypt(AeadDecryptRequest {
+            ciphertext: encrypted.ciphertext,
+            key: key.as_str().into(),
+            nonce: encrypted.nonce,
+            additional_data,
+        }).unwrap();
+        prop_assert_eq!(decrypted.plaintext.expose_secret(), plaintext.as_str());
+    }
+
+    #[test]
+    fn aead_rejects_any_single_bit_flip(
+        plaintext in ".{0,64}",
+        key in prop::array::uniform32(any::<u8>()),
+        bit in any::<prop::sample::Index>(),
+    ) {
+        let key = STANDARD.encode(key);
+        let encrypted = service().aead_encrypt(AeadEncryptRequest {
+            plaintext: plaintext.as_str().into(),
+            key: key.as_str().into(),
+            additional_data: None,
+        }).unwrap();
+        let mut ciphertext = STANDARD.decode(&encrypted.ciphertext).unwrap();
+        let bit = bit.index(ciphertext.len() * 8);
+        ciphertext[bit / 8] ^= 1 << (bit % 8);
+
+        let result = service().aead_decrypt(AeadDecryptRequest {
+            ciphertext: STANDARD.encode(ciphertext),
+            key: key.as_str().into(),
+            nonce: encrypted.nonce,
+            additional_data: None,
+        });
+        prop_assert!(matches!(result, Err(CryptoError::DecryptionFailed(_))));
+    }
+
+    #[test]
+    fn key_wrap_round_trip(
+        master_key in prop::collection::vec(any::<u8>(), 1..64),
+        user_key in prop::collection::vec(any::<u8>(), 0..64),
+    ) {
+        let master_key = STANDARD.encode(master_key);
+        let wrapped = service().key_wrap(KeyWrapRequest {
+            master_key: master_key.as_str().into(),
+            user_key: STANDARD.encode(&user_key).into(),
+        }).unwrap();
+        let unwrapped = service().key_unwrap(KeyUnwrapRequest {
+            master_key: master_key.as_str().into(),
+            wrapped_key: wrapped.wrapped_key,
+            salt: wrapped.salt,
+        }).unwrap();
+        prop_assert_eq!(STANDARD.decode(unwrapped.unwrapped_key.expose_secret()).unwrap(), user_key);
+    }
+
+    #[test]
+    fn key_unwrap_fails_under_another_master_key(
+        master_key in prop::array::uniform32(any::<u8>()),
+        other_key in prop::array::uniform32(any::<u8>()),
+        user_key in prop::array::uniform32(any::<u8>()),
+    ) {
+        prop_assume!(master_key != other_key);
+        let wrapped = service().key_wrap(KeyWrapRequest {
+            master_key: STANDARD.encode(master_key).into(),
+            user_key: STANDARD.encode(user_key).into(),
+        }).unwrap();
+        let result = service().key_unwrap(KeyUnwrapRequest {
+            master_key: STANDARD.encode(other_key).into(),
+            wrapped_key: wrapped.wrapped_key,
+            salt: wrapped.salt,
+        });
+        prop_assert!(matches!(result, Err(CryptoError::DecryptionFailed(_))));
+    }
+
+    #[test]
+    fn shamir_any_k_of_n_recovers_the_secret((secret, k, n, indices) in split_case()) {
+        let split = service().shamir_split(ShamirSplitRequest {
+            secret: STANDARD.encode(&secret).into(),
+            threshold: k,
+            shares: n,
+        }).unwrap();
+        prop_assert_eq!(split.shares.len(), n as usize);
+
+        let combined = service().shamir_combine(ShamirCombineRequest {
+            shares: indices.iter().map(|&i| split.shares[i].clone()).collect(),
+        }).unwrap();
+        prop_assert_eq!(STANDARD.decode(combined.secret.expose_secret()).unwrap(), secret);
+    }
+}
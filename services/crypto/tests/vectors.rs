@@ -0,0 +1,403 @@
+//! Known-answer vectors loaded from `tests/vectors/*.json`.
+//!
+//! The published vectors (RFC 9106, RFC 5869, draft-irtf-cfrg-xchacha, RFC 9180, RFC 8032,
+//! RFC 6962) check
+//! the primitives; `v1_formats.json` freezes what the v1 service produced, so
+//! any change in derived output or wire encoding fails here first.
+
+use argon2::{Algorithm, Argon2, AssociatedData, ParamsBuilder, Version};
+use base64::{Engine as _, engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD}};
+use ed25519_dalek::{Signer, SigningKey};
+use hkdf::Hkdf;
+use last_words_crypto::aead::{self, pure, xchacha20poly1305_ietf};
+use last_words_crypto::hpke;
+use last_words_crypto::merkle::{self, Hash};
+use last_words_crypto::{
+    AeadDecryptRequest, AeadEncryptRequest, CryptoBoundaryService, KdfRequest, KeyUnwrapRequest,
+};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use sha2::Sha256;
+
+fn load<T: DeserializeOwned>(name: &str) -> T {
+    let path = format!("{}/tests/vectors/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let json = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    serde_json::from_str(&json).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+fn unhex(s: &str) -> Vec<u8> {
+    hex::decode(s).unwrap()
+}
+
+#[derive(Deserialize)]
+struct VectorFile<T> {
+    vectors: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct Argon2Vector {
+    algorithm: String,
+    version: u32,
+    memory: u32,
+    iterations: u32,
+    parallelism: u32,
+    password: String,
+    salt: String,
+    secret: String,
+    associated_data: String,
+    tag: String,
+}
+
+#[test]
+fn rfc9106_argon2() {
+    let file: VectorFile<Argon2Vector> = load("argon2.json");
+    for v in file.vectors {
+        let algorithm = match v.algorithm.as_str() {
+            "argon2d" => Algorithm::Argon2d,
+            "argon2i" => Algorithm::Argon2i,
+            "argon2id" => Algorithm::Argon2id,
+            other => panic!("unknown algorithm {}", other),
+        };
+        let version = Version::try_from(v.version).unwrap();
+        let ad = unhex(&v.associated_data);
+        let expected = unhex(&v.tag);
+        let params = ParamsBuilder::new()
+            .m_cost(v.memory)
+            .t_cost(v.iterations)
+            .p_cost(v.parallelism)
+            .data(AssociatedData::new(&ad).unwrap())
+            .output_len(expected.len())
+            .build()
+            .unwrap();
+        let secret = unhex(&v.secret);
+        let argon2 = Argon2::new_with_secret(&secret, algorithm, version, params).unwrap();
+
+        let mut tag = vec![0u8; expected.len()];
+        argon2.hash_password_into(&unhex(&v.password), &unhex(&v.salt), &mut tag).unwrap();
+        assert_eq!(hex::encode(tag), v.tag, "{}", v.algorithm);
+    }
+}
+
+#[derive(Deserialize)]
+struct HkdfVector {
+    name: String,
+    ikm: String,
+    salt: String,
+    info: String,
+    prk: String,
+    okm: String,
+}
+
+#[test]
+fn rfc5869_hkdf_sha256() {
+    let file: VectorFile<HkdfVector> = load("hkdf_sha256.json");
+    for v in file.vectors {
+        // An empty salt means "not provided", which HKDF treats as zeros.
+        let salt = unhex(&v.salt);
+        let salt = (!salt.is_empty()).then_some(salt.as_slice());
+        let (prk, hk) = Hkdf::<Sha256>::extract(salt, &unhex(&v.ikm));
+        assert_eq!(hex::encode(prk), v.prk, "{}: PRK", v.name);
+
+        let mut okm = vec![0u8; v.okm.len() / 2];
+        hk.expand(&unhex(&v.info), &mut okm).unwrap();
+        assert_eq!(hex::encode(okm), v.okm, "{}: OKM", v.name);
+    }
+}
+
+#[derive(Deserialize)]
+struct XChaChaVector {
+    key: String,
+    nonce: String,
+    aad: String,
+    plaintext: String,
+    ciphertext: String,
+    tag: String,
+}
+
+#[test]
+fn xchacha20poly1305_draft_vectors() {
+    aead::init().unwrap();
+    let file: VectorFile<XChaChaVector> = load("xchacha20poly1305.json");
+    for v in file.vectors {
+        let (key, nonce, aad, plaintext) = (unhex(&v.key), unhex(&v.nonce), unhex(&v.aad), unhex(&v.plaintext));
+        let expected = format!("{}{}", v.ciphertext, v.tag);
+
+        // The backend the service uses (libsodium when enabled) ...
+        let k = xchacha20poly1305_ietf::Key::from_slice(&key).unwrap();
+        let n = xchacha20poly1305_ietf::Nonce::from_slice(&nonce).unwrap();
+        let sealed = xchacha20poly1305_ietf::seal(&plaintext, Some(&aad), &n, &k);
+        assert_eq!(hex::encode(&sealed), expected);
+        assert_eq!(xchacha20poly1305_ietf::open(&sealed, Some(&aad), &n, &k).unwrap(), plaintext);
+
+        // ... and the pure-Rust backend used by the wasm build.
+        let k = pure::Key::from_slice(&key).unwrap();
+        let n = pure::Nonce::from_slice(&nonce).unwrap();
+        let sealed = pure::seal(&plaintext, Some(&aad), &n, &k);
+        assert_eq!(hex::encode(&sealed), expected);
+        assert_eq!(pure::open(&sealed, Some(&aad), &n, &k).unwrap(), plaintext);
+    }
+}
+
+#[derive(Deserialize)]
+#[allow(non_snake_case)]
+struct HpkeVector {
+    mode: u8,
+    info: String,
+    ikmR: String,
+    ikmE: String,
+    skRm: String,
+    skEm: String,
+    pkRm: String,
+    pkEm: String,
+    encryptions: Vec<HpkeEncryption>,
+    exports: Vec<HpkeExport>,
+}
+
+#[derive(Deserialize)]
+struct HpkeEncryption {
+    sequence: u64,
+    aad: String,
+    pt: String,
+    ct: String,
+}
+
+#[derive(Deserialize)]
+#[allow(non_snake_case)]
+struct HpkeExport {
+    exporter_context: String,
+    L: usize,
+    exported_value: String,
+}
+
+fn x25519_secret(hex: &str) -> hpke::StaticSecret {
+    hpke::StaticSecret::from(<[u8; 32]>::try_from(unhex(hex)).unwrap())
+}
+
+#[test]
+fn rfc9180_hpke() {
+    let file: VectorFile<HpkeVector> = load("hpke.json");
+    for v in file.vectors {
+        // Base mode only; auth mode is covered by the round trips in src/hpke.rs.
+        assert_eq!(v.mode, 0);
+        let sk_r = x25519_secret(&v.skRm);
+        let sk_e = x25519_secret(&v.skEm);
+        assert_eq!(hpke::derive_key_pair(&unhex(&v.ikmR)).unwrap().to_bytes(), sk_r.to_bytes());
+        assert_eq!(hpke::derive_key_pair(&unhex(&v.ikmE)).unwrap().to_bytes(), sk_e.to_bytes());
+        let pk_r = hpke::PublicKey::from(&sk_r);
+        assert_eq!(hex::encode(pk_r.as_bytes()), v.pkRm);
+
+        let info = unhex(&v.info);
+        let (enc, mut sender) = hpke::setup_sender_with_ephemeral(&pk_r, &info, None, &sk_e).unwrap();
+        assert_eq!(hex::encode(enc), v.pkEm);
+        let mut receiver = hpke::setup_receiver(&enc, &sk_r, &info, None).unwrap();
+
+        // Messages must be processed in order; the ones in between are filler.
+        let mut sequence = 0;
+        for e in &v.encryptions {
+            let (aad, pt) = (unhex(&e.aad), unhex(&e.pt));
+            while sequence < e.sequence {
+                let filler = sender.seal(b"", b"").unwrap();
+                receiver.open(b"", &filler).unwrap();
+                sequence += 1;
+            }
+            let ct = sender.seal(&aad, &pt).unwrap();
+            assert_eq!(hex::encode(&ct), e.ct, "sequence {}", e.sequence);
+            assert_eq!(receiver.open(&aad, &ct).unwrap(), pt);
+            sequence += 1;
+        }
+
+        for e in &v.exports {
+            let mut exported = vec![0u8; e.L];
+            sender.export(&unhex(&e.exporter_context), &mut exported).unwrap();
+            assert_eq!(hex::encode(&exported), e.exported_value);
+            receiver.export(&unhex(&e.exporter_context), &mut exported).unwrap();
+            assert_eq!(hex::encode(&exported), e.exported_value);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Ed25519Vector {
+    secret_key: String,
+    public_key: String,
+    message: String,
+    signature: String,
+}
+
+#[test]
+fn rfc8032_ed25519() {
+    let file: VectorFile<Ed25519Vector> = load("ed25519.json");
+    let service = CryptoBoundaryService::new();
+    for v in file.vectors {
+        let seed = unhex(&v.secret_key);
+        let signing_key = SigningKey::from_bytes(&seed.clone().try_into().unwrap());
+        assert_eq!(hex::encode(signing_key.verifying_key().as_bytes()), v.public_key);
+        let signature = signing_key.sign(&unhex(&v.message));
+        assert_eq!(hex::encode(signature.to_bytes()), v.signature);
+
+        // The service derives the same public key from an imported seed.
+        let jwk = service.import_signing_key(&seed).unwrap();
+        assert_eq!(URL_SAFE_NO_PAD.decode(&jwk.x).unwrap(), unhex(&v.public_key));
+    }
+}
+
+#[derive(Deserialize)]
+struct MerkleFile {
+    leaves: Vec<String>,
+    roots: Vec<String>,
+    inclusion: Vec<InclusionVector>,
+    consistency: Vec<ConsistencyVector>,
+}
+
+#[derive(Deserialize)]
+struct InclusionVector {
+    index: usize,
+    tree_size: usize,
+    path: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ConsistencyVector {
+    first: usize,
+    second: usize,
+    proof: Vec<String>,
+}
+
+#[test]
+fn rfc6962_merkle_tree() {
+    let file: MerkleFile = load("rfc6962.json");
+    let leaves: Vec<Hash> = file.leaves.iter().map(|leaf| merkle::leaf_hash(&unhex(leaf))).collect();
+    let decode = |hashes: &[String]| -> Vec<Hash> { hashes.iter().map(|h| unhex(h).try_into().unwrap()).collect() };
+    for (size, root) in file.roots.iter().enumerate().map(|(i, root)| (i + 1, root)) {
+        assert_eq!(hex::encode(merkle::root(&leaves[..size])), *root, "root of {} leaves", size);
+    }
+    for v in file.inclusion {
+        let tree = &leaves[..v.tree_size];
+        let path = decode(&v.path);
+        assert_eq!(merkle::inclusion_proof(tree, v.index), path);
+        let root = merkle::root(tree);
+        assert!(merkle::verify_inclusion(&tree[v.index], v.index as u64, v.tree_size as u64, &path, &root));
+    }
+    for v in file.consistency {
+        let proof = decode(&v.proof);
+        assert_eq!(merkle::consistency_proof(&leaves[..v.second], v.first), proof);
+        let (first, second) = (merkle::root(&leaves[..v.first]), merkle::root(&leaves[..v.second]));
+        assert!(merkle::verify_consistency(v.first as u64, v.second as u64, &first, &second, &proof));
+    }
+}
+
+#[derive(Deserialize)]
+struct V1Formats {
+    kdf: Vec<KdfVector>,
+    aead: Vec<AeadVector>,
+    key_wrap: Vec<WrapVector>,
+}
+
+#[derive(Deserialize)]
+struct KdfVector {
+    password: String,
+    salt: String,
+    memory: u32,
+    iterations: u32,
+    parallelism: u32,
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct AeadVector {
+    key: String,
+    plaintext: String,
+    additional_data: Option<String>,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Deserialize)]
+struct WrapVector {
+    master_key: String,
+    user_key: String,
+    salt: String,
+    wrapped_key: String,
+}
+
+#[test]
+fn frozen_v1_kdf() {
+    let service = CryptoBoundaryService::new();
+    let formats: V1Formats = load("v1_formats.json");
+    for v in formats.kdf {
+        let response = service.kdf_argon2id(KdfRequest {
+            password: v.password.as_str().into(),
+            salt: Some(v.salt.clone()),
+            memory: Some(v.memory),
+            iterations: Some(v.iterations),
+            parallelism: Some(v.parallelism),
+        }).unwrap();
+        assert_eq!(response.hash.expose_secret(), v.hash);
+        assert_eq!(response.salt, v.salt);
+    }
+}
+
+#[test]
+fn frozen_v1_aead() {
+    let service = CryptoBoundaryService::new();
+    let formats: V1Formats = load("v1_formats.json");
+    for v in formats.aead {
+        let decrypted = service.aead_decrypt(AeadDecryptRequest {
+            ciphertext: v.ciphertext.clone(),
+            key: v.key.as_str().into(),
+            nonce: v.nonce.clone(),
+            additional_data: v.additional_data.clone(),
+        }).unwrap();
+        assert_eq!(decrypted.plaintext.expose_secret(), v.plaintext);
+
+        // The ciphertext is exactly XChaCha20-Poly1305 under the stored nonce.
+        let key = xchacha20poly1305_ietf::Key::from_slice(&STANDARD.decode(&v.key).unwrap()).unwrap();
+        let nonce = xchacha20poly1305_ietf::Nonce::from_slice(&STANDARD.decode(&v.nonce).unwrap()).unwrap();
+        let ad = v.additional_data.as_deref().unwrap_or("");
+        let sealed = xchacha20poly1305_ietf::seal(v.plaintext.as_bytes(), Some(ad.as_bytes()), &nonce, &key);
+        assert_eq!(STANDARD.encode(sealed), v.ciphertext);
+
+        // Fresh encryptions of the same input still decrypt to it.
+        let encrypted = service.aead_encrypt(AeadEncryptRequest {
+            plaintext: v.plaintext.as_str().into(),
+            key: v.key.as_str().into(),
+            additional_data: v.additional_data.clone(),
+        }).unwrap();
+        let decrypted = service.aead_decrypt(AeadDecryptRequest {
+            ciphertext: encrypted.ciphertext,
+            key: v.key.as_str().into(),
+            nonce: encrypted.nonce,
+            additional_data: v.additional_data,
+        }).unwrap();
+        assert_eq!(decrypted.plaintext.expose_secret(), v.plaintext);
+    }
+}
+
+#[test]
+fn frozen_v1_key_wrap() {
+    let service = CryptoBoundaryService::new();
+    let formats: V1Formats = load("v1_formats.json");
+    for v in formats.key_wrap {
+        let master_key = unhex(&v.master_key);
+        let user_key = unhex(&v.user_key);
+
+        let unwrapped = service.key_unwrap(KeyUnwrapRequest {
+            master_key: STANDARD.encode(&master_key).into(),
+            wrapped_key: v.wrapped_key.clone(),
+            salt: v.salt.clone(),
+        }).unwrap();
+        assert_eq!(STANDARD.decode(unwrapped.unwrapped_key.expose_secret()).unwrap(), user_key);
+
+        // v1 layout: nonce (24) || XChaCha20-Poly1305(HKDF-SHA256(salt, master, "key-wrap"), user key).
+        let wrapped = STANDARD.decode(&v.wrapped_key).unwrap();
+        let (nonce, ciphertext) = wrapped.split_at(xchacha20poly1305_ietf::NONCEBYTES);
+        let mut derived = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(&STANDARD.decode(&v.salt).unwrap()), &master_key)
+            .expand(b"key-wrap", &mut derived)
+            .unwrap();
+        let key = xchacha20poly1305_ietf::Key::from_slice(&derived).unwrap();
+        let nonce = xchacha20poly1305_ietf::Nonce::from_slice(nonce).unwrap();
+        assert_eq!(xchacha20poly1305_ietf::seal(&user_key, None, &nonce, &key), ciphertext);
+    }
+}